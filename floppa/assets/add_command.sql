@@ -1,3 +1,3 @@
-INSERT into commands(name, owner, type, registry, added, data) 
-VALUES(?, ?, ?, ?, ?, ?)
+INSERT into commands(name, owner, type, registry, added, data, uses, last_used, editor, edited) 
+VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
 RETURNING id;
\ No newline at end of file
@@ -1,3 +1,3 @@
-SELECT commands.name, commands.id, owner, type as ty, data, added, registries.name AS registry
+SELECT commands.name, commands.id, owner, type as ty, data, added, uses, last_used, editor, edited, registries.name AS registry
 FROM commands, registries
 WHERE registries.id = commands.registry;
\ No newline at end of file
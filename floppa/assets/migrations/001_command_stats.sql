@@ -0,0 +1,3 @@
+ALTER TABLE commands ADD COLUMN uses INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE commands ADD COLUMN editor INTEGER NULL;
+ALTER TABLE commands ADD COLUMN edited INTEGER NULL;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS schedules(
+    id       INTEGER  PRIMARY KEY NOT NULL,
+    owner    INTEGER  NOT NULL,
+    guild    INTEGER,
+    channel  INTEGER  NOT NULL,
+    action   TEXT     NOT NULL,
+    body     TEXT     NOT NULL,
+    cron     TEXT,
+    next_run INTEGER  NOT NULL,
+    paused   INTEGER  NOT NULL DEFAULT 0
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS reminders(
+    id      INTEGER  PRIMARY KEY NOT NULL,
+    user    INTEGER  NOT NULL,
+    guild   INTEGER,
+    channel INTEGER  NOT NULL,
+    message INTEGER  NOT NULL,
+    text    TEXT     NOT NULL,
+    added   INTEGER  NOT NULL,
+    due     INTEGER  NOT NULL,
+    dm      INTEGER  NOT NULL DEFAULT 0
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS responders(
+    id       INTEGER  PRIMARY KEY NOT NULL,
+    owner    INTEGER  NOT NULL,
+    guild    INTEGER,
+    channel  INTEGER,
+    trigger  TEXT     NOT NULL,
+    pattern  TEXT     NOT NULL,
+    action   TEXT     NOT NULL,
+    body     TEXT     NOT NULL,
+    cooldown INTEGER  NOT NULL DEFAULT 0
+);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS reaction_resets(
+    id    INTEGER  PRIMARY KEY NOT NULL,
+    user  INTEGER  NOT NULL,
+    time  INTEGER  NOT NULL,
+    count INTEGER  NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS reactions(
+    id      INTEGER  PRIMARY KEY NOT NULL,
+    guild   INTEGER,
+    channel INTEGER  NOT NULL,
+    user    INTEGER  NOT NULL,
+    time    INTEGER  NOT NULL,
+    reset   INTEGER  REFERENCES reaction_resets(id)
+);
+
+CREATE INDEX IF NOT EXISTS reactions_by_time ON reactions(reset, time);
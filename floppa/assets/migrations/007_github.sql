@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS github_repos(
+    guild INTEGER  PRIMARY KEY NOT NULL,
+    repo  TEXT     NOT NULL
+);
@@ -0,0 +1,9 @@
+ALTER TABLE responders ADD COLUMN counts INTEGER NOT NULL DEFAULT 0;
+
+-- The flop responder is the one moved out of the emoji config, which has no owner
+UPDATE responders SET counts = 1
+WHERE id = (SELECT MIN(id) FROM responders WHERE owner = 1 AND guild IS NULL AND action = 'react');
+
+ALTER TABLE reactions ADD COLUMN message INTEGER;
+
+CREATE UNIQUE INDEX IF NOT EXISTS reactions_by_message ON reactions(message);
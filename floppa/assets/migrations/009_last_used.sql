@@ -0,0 +1 @@
+ALTER TABLE commands ADD COLUMN last_used INTEGER NULL;
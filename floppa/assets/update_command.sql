@@ -3,5 +3,9 @@ SET name = ?,
 owner = ?, 
 type = ?, 
 registry = ?, 
-data = ?
+data = ?,
+uses = ?,
+last_used = ?,
+editor = ?,
+edited = ?
 WHERE id = ?;
\ No newline at end of file
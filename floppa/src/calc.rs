@@ -0,0 +1,800 @@
+use std::fmt::Display;
+
+use crate::gregtech::{tier_for, TIERS};
+
+/// The longest expression that will be evaluated
+const MAX_LENGTH: usize = 500;
+/// How deeply brackets, powers and negatives can be nested
+const MAX_DEPTH: usize = 64;
+/// The largest power any unit can end up with, either way
+const MAX_POWER: u8 = 64;
+
+/// The base unit of each dimension, energy, time and volume, in the order exponents are kept
+const BASE_UNITS: [&str; 3] = ["EU", "t", "mB"];
+
+/// Units, with their size in the base unit of their dimension
+const UNITS: &[(&str, f64, [i8; 3])] = &[
+    ("EU", 1.0, [1, 0, 0]),
+    // GregTech converts 1 EU to 4 RF
+    ("RF", 0.25, [1, 0, 0]),
+    ("FE", 0.25, [1, 0, 0]),
+    ("t", 1.0, [0, 1, 0]),
+    ("tick", 1.0, [0, 1, 0]),
+    ("ticks", 1.0, [0, 1, 0]),
+    ("s", 20.0, [0, 1, 0]),
+    ("sec", 20.0, [0, 1, 0]),
+    ("secs", 20.0, [0, 1, 0]),
+    ("second", 20.0, [0, 1, 0]),
+    ("seconds", 20.0, [0, 1, 0]),
+    ("min", 1200.0, [0, 1, 0]),
+    ("mins", 1200.0, [0, 1, 0]),
+    ("minute", 1200.0, [0, 1, 0]),
+    ("minutes", 1200.0, [0, 1, 0]),
+    ("h", 72000.0, [0, 1, 0]),
+    ("hr", 72000.0, [0, 1, 0]),
+    ("hour", 72000.0, [0, 1, 0]),
+    ("hours", 72000.0, [0, 1, 0]),
+    ("mB", 1.0, [0, 0, 1]),
+    ("L", 1.0, [0, 0, 1]),
+    ("B", 1000.0, [0, 0, 1]),
+    ("bucket", 1000.0, [0, 0, 1]),
+    ("buckets", 1000.0, [0, 0, 1]),
+    ("pi", std::f64::consts::PI, [0, 0, 0]),
+    ("e", std::f64::consts::E, [0, 0, 0]),
+];
+/// Units that can have an SI prefix, like `kEU` or `ms`
+const PREFIXABLE: &[&str] = &["EU", "RF", "FE", "s", "B", "L"];
+const PREFIXES: &[(char, f64)] = &[
+    ('k', 1e3),
+    ('M', 1e6),
+    ('G', 1e9),
+    ('T', 1e12),
+    ('P', 1e15),
+    ('m', 1e-3),
+    ('u', 1e-6),
+    ('µ', 1e-6),
+];
+const FUNCTIONS: &[&str] = &["sqrt", "abs", "floor", "ceil", "round", "ln", "log"];
+
+/// A number with units, kept in the base units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    /// The exponent of each dimension, like `[1, -1, 0]` for EU/t
+    pub dims: [i8; 3],
+}
+
+/// The answer to an expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Answer {
+    /// It was worked out in the best units for it
+    Quantity(Quantity),
+    /// It was converted with `in` or `to`, into the units written after it
+    Converted(f64, String),
+}
+
+/// Something wrong with an expression, with the characters it is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalcError {
+    /// Where the problem starts, in characters
+    pub start: usize,
+    /// How many characters it covers
+    pub len: usize,
+    pub msg: String,
+}
+
+impl CalcError {
+    fn new(token: &Token, msg: impl Into<String>) -> Self {
+        Self {
+            start: token.start,
+            len: token.len,
+            msg: msg.into(),
+        }
+    }
+
+    /// Points to the problem under the expression, like the errors from stoik
+    pub fn format_msg(&self, expr: &str) -> String {
+        format!(
+            "Invalid expression\n{expr}\n{}{} {}",
+            " ".repeat(self.start),
+            "^".repeat(self.len.max(1)),
+            self.msg
+        )
+    }
+}
+
+impl Display for CalcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Ident(String),
+    Op(char),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    start: usize,
+    len: usize,
+}
+
+/// Evaluates an expression, like `512 EU/t * 20s in RF`
+pub fn evaluate(expr: &str) -> Result<Answer, CalcError> {
+    let length = expr.chars().count();
+    if length > MAX_LENGTH {
+        return Err(CalcError {
+            start: 0,
+            len: 0,
+            msg: format!("Expressions can be at most {MAX_LENGTH} characters long"),
+        });
+    }
+
+    let mut parser = Parser {
+        tokens: tokenise(expr)?,
+        pos: 0,
+        depth: 0,
+    };
+    let result = parser.sum()?;
+
+    let answer = match &parser.peek().tok {
+        Tok::Ident(x) if x == "in" || x == "to" => {
+            parser.next();
+            let start = parser.peek().start;
+            let target = parser.sum()?;
+            let end = parser.peek().start;
+            let token = Token {
+                tok: Tok::End,
+                start,
+                len: end - start,
+            };
+            if target.dims != result.dims {
+                return Err(CalcError::new(
+                    &token,
+                    format!(
+                        "Can't convert {} into {}",
+                        unit_name(result.dims),
+                        unit_name(target.dims)
+                    ),
+                ));
+            }
+            if target.value == 0.0 {
+                return Err(CalcError::new(&token, "Can't convert into nothing"));
+            }
+            let text = expr
+                .chars()
+                .skip(start)
+                .take(end - start)
+                .collect::<String>();
+            Answer::Converted(result.value / target.value, text.trim().to_owned())
+        }
+        _ => Answer::Quantity(result),
+    };
+    if let Tok::End = parser.peek().tok {
+    } else {
+        return Err(CalcError::new(parser.peek(), "Expected an operator"));
+    }
+
+    let value = match &answer {
+        Answer::Quantity(x) => x.value,
+        Answer::Converted(x, _) => *x,
+    };
+    if !value.is_finite() {
+        return Err(CalcError {
+            start: 0,
+            len: length,
+            msg: "The answer is too big, or isn't a number".to_string(),
+        });
+    }
+    Ok(answer)
+}
+
+fn tokenise(expr: &str) -> Result<Vec<Token>, CalcError> {
+    let chars = expr.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let tok = if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let mut text = String::new();
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_')
+            {
+                if chars[i] != '_' {
+                    text.push(chars[i]);
+                }
+                i += 1;
+            }
+            // Only an exponent if there are digits after it, so `2e` is 2 times e
+            if matches!(chars.get(i), Some('e' | 'E')) {
+                let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                    text.extend(&chars[i..i + 1 + sign]);
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                }
+            }
+            match text.parse() {
+                Ok(x) => Tok::Num(x),
+                Err(_) => {
+                    return Err(CalcError {
+                        start,
+                        len: i - start,
+                        msg: "Invalid number".to_string(),
+                    })
+                }
+            }
+        } else if c.is_alphabetic() {
+            let mut text = String::new();
+            while i < chars.len() && chars[i].is_alphabetic() {
+                text.push(chars[i]);
+                i += 1;
+            }
+            Tok::Ident(text)
+        } else if "+-*/^%(),".contains(c) {
+            i += 1;
+            Tok::Op(c)
+        } else {
+            return Err(CalcError {
+                start,
+                len: 1,
+                msg: format!("Unexpected `{c}`"),
+            });
+        };
+        tokens.push(Token {
+            tok,
+            start,
+            len: i - start,
+        });
+    }
+    tokens.push(Token {
+        tok: Tok::End,
+        start: chars.len(),
+        len: 1,
+    });
+    Ok(tokens)
+}
+
+/// Looks up a unit or constant, with an SI prefix if it can have one
+fn unit(name: &str) -> Option<Quantity> {
+    let found = |name: &str| {
+        UNITS
+            .iter()
+            .find(|(x, _, _)| *x == name)
+            .map(|(_, value, dims)| Quantity {
+                value: *value,
+                dims: *dims,
+            })
+    };
+    if let Some(unit) = found(name) {
+        return Some(unit);
+    }
+
+    let mut chars = name.chars();
+    let prefix = chars.next()?;
+    let rest = chars.as_str();
+    let (_, factor) = PREFIXES.iter().find(|(x, _)| *x == prefix)?;
+    if !PREFIXABLE.contains(&rest) {
+        return None;
+    }
+    found(rest).map(|x| Quantity {
+        value: x.value * factor,
+        ..x
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        self.pos += 1;
+        token
+    }
+
+    fn is_op(&self, op: char) -> bool {
+        self.peek().tok == Tok::Op(op)
+    }
+
+    /// Goes one level deeper, erroring if it is too deep
+    fn enter(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(CalcError::new(self.peek(), "This is nested too deeply"));
+        }
+        Ok(())
+    }
+
+    /// `a + b` and `a - b`
+    fn sum(&mut self) -> Result<Quantity, CalcError> {
+        let mut left = self.product()?;
+        while self.is_op('+') || self.is_op('-') {
+            let op = self.next();
+            let right = self.product()?;
+            if left.dims != right.dims {
+                return Err(CalcError::new(
+                    &op,
+                    format!(
+                        "Can't add or subtract {} and {}",
+                        unit_name(left.dims),
+                        unit_name(right.dims)
+                    ),
+                ));
+            }
+            left.value = match op.tok {
+                Tok::Op('+') => left.value + right.value,
+                _ => left.value - right.value,
+            };
+        }
+        Ok(left)
+    }
+
+    /// `a * b`, `a / b` and `a % b`
+    fn product(&mut self) -> Result<Quantity, CalcError> {
+        let mut left = self.unary()?;
+        while self.is_op('*') || self.is_op('/') || self.is_op('%') {
+            let op = self.next();
+            let right = self.unary()?;
+            left = match op.tok {
+                Tok::Op('*') => multiply(left, right, 1, &op)?,
+                _ if right.value == 0.0 => return Err(CalcError::new(&op, "Division by zero")),
+                Tok::Op('/') => multiply(left, right, -1, &op)?,
+                _ if left.dims != right.dims => {
+                    return Err(CalcError::new(
+                        &op,
+                        format!(
+                            "Can't take the remainder of {} and {}",
+                            unit_name(left.dims),
+                            unit_name(right.dims)
+                        ),
+                    ))
+                }
+                _ => Quantity {
+                    value: left.value % right.value,
+                    dims: left.dims,
+                },
+            };
+        }
+        Ok(left)
+    }
+
+    /// `-a` and `+a`
+    fn unary(&mut self) -> Result<Quantity, CalcError> {
+        if self.is_op('-') || self.is_op('+') {
+            let op = self.next();
+            self.enter()?;
+            let mut value = self.unary()?;
+            self.depth -= 1;
+            if op.tok == Tok::Op('-') {
+                value.value = -value.value;
+            }
+            return Ok(value);
+        }
+        self.power()
+    }
+
+    /// `a ^ b`, which is right associative
+    fn power(&mut self) -> Result<Quantity, CalcError> {
+        let base = self.postfix()?;
+        if !self.is_op('^') {
+            return Ok(base);
+        }
+        let op = self.next();
+        self.enter()?;
+        let exponent = self.unary()?;
+        self.depth -= 1;
+        raise(base, exponent, &op)
+    }
+
+    /// A value followed by units, like `20 EU` or `3 s^2`
+    fn postfix(&mut self) -> Result<Quantity, CalcError> {
+        let mut value = self.atom()?;
+        loop {
+            let token = self.peek().clone();
+            let Tok::Ident(name) = &token.tok else {
+                break;
+            };
+            let Some(unit) = unit(name) else {
+                break;
+            };
+            self.next();
+            let unit = if self.is_op('^') {
+                let op = self.next();
+                self.enter()?;
+                let exponent = self.unary()?;
+                self.depth -= 1;
+                raise(unit, exponent, &op)?
+            } else {
+                unit
+            };
+            value = multiply(value, unit, 1, &token)?;
+        }
+        Ok(value)
+    }
+
+    /// A number, unit, function or bracketed expression
+    fn atom(&mut self) -> Result<Quantity, CalcError> {
+        let token = self.next();
+        match &token.tok {
+            Tok::Num(value) => Ok(Quantity {
+                value: *value,
+                dims: [0; 3],
+            }),
+            Tok::Op('(') => {
+                self.enter()?;
+                let value = self.sum()?;
+                self.depth -= 1;
+                self.close(&token)?;
+                Ok(value)
+            }
+            Tok::Ident(name) if FUNCTIONS.contains(&name.as_str()) => {
+                let open = self.next();
+                if open.tok != Tok::Op('(') {
+                    return Err(CalcError::new(
+                        &open,
+                        format!("Expected `(` after `{name}`"),
+                    ));
+                }
+                self.enter()?;
+                let arg = self.sum()?;
+                self.depth -= 1;
+                self.close(&open)?;
+                function(name, arg, &token)
+            }
+            Tok::Ident(name) => unit(name).ok_or_else(|| {
+                CalcError::new(&token, format!("`{name}` is not a unit or function"))
+            }),
+            Tok::End => Err(CalcError::new(&token, "Expected a number")),
+            Tok::Op(c) => Err(CalcError::new(&token, format!("Unexpected `{c}`"))),
+        }
+    }
+
+    /// Expects the `)` that closes `open`
+    fn close(&mut self, open: &Token) -> Result<(), CalcError> {
+        if self.is_op(')') {
+            self.next();
+            Ok(())
+        } else {
+            Err(CalcError::new(open, "Unpaired parenthesis"))
+        }
+    }
+}
+
+/// Multiplies, or divides if `sign` is -1
+fn multiply(
+    left: Quantity,
+    right: Quantity,
+    sign: i8,
+    token: &Token,
+) -> Result<Quantity, CalcError> {
+    let mut dims = left.dims;
+    for (dim, other) in dims.iter_mut().zip(right.dims) {
+        *dim = other
+            .checked_mul(sign)
+            .and_then(|x| dim.checked_add(x))
+            .and_then(check_power)
+            .ok_or_else(|| power_error(token))?;
+    }
+    Ok(Quantity {
+        value: if sign > 0 {
+            left.value * right.value
+        } else {
+            left.value / right.value
+        },
+        dims,
+    })
+}
+
+/// Raises to a power, units can only be raised to whole numbers
+fn raise(base: Quantity, exponent: Quantity, op: &Token) -> Result<Quantity, CalcError> {
+    if exponent.dims != [0; 3] {
+        return Err(CalcError::new(op, "Powers can't have units"));
+    }
+    let mut dims = base.dims;
+    if dims != [0; 3] {
+        let whole = exponent.value.round();
+        if whole != exponent.value || whole.abs() > f64::from(MAX_POWER) {
+            return Err(CalcError::new(
+                op,
+                "Units can only be raised to small whole numbers",
+            ));
+        }
+        for dim in &mut dims {
+            *dim = dim
+                .checked_mul(whole as i8)
+                .and_then(check_power)
+                .ok_or_else(|| power_error(op))?;
+        }
+    }
+    Ok(Quantity {
+        value: base.value.powf(exponent.value),
+        dims,
+    })
+}
+
+/// Keeps the power of a unit within [`MAX_POWER`]
+fn check_power(power: i8) -> Option<i8> {
+    (power.unsigned_abs() <= MAX_POWER).then_some(power)
+}
+
+fn power_error(token: &Token) -> CalcError {
+    CalcError::new(
+        token,
+        format!("Units can't be raised to more than the power of {MAX_POWER}"),
+    )
+}
+
+fn function(name: &str, arg: Quantity, token: &Token) -> Result<Quantity, CalcError> {
+    let value = arg.value;
+    let result = match name {
+        "sqrt" => {
+            if arg.dims.iter().any(|x| x % 2 != 0) {
+                return Err(CalcError::new(
+                    token,
+                    format!("Can't take the square root of {}", unit_name(arg.dims)),
+                ));
+            }
+            return Ok(Quantity {
+                value: value.sqrt(),
+                dims: arg.dims.map(|x| x / 2),
+            });
+        }
+        "abs" => value.abs(),
+        "floor" => value.floor(),
+        "ceil" => value.ceil(),
+        "round" => value.round(),
+        _ if arg.dims != [0; 3] => {
+            return Err(CalcError::new(token, format!("`{name}` can't take units")))
+        }
+        "ln" => value.ln(),
+        _ => value.log10(),
+    };
+    Ok(Quantity {
+        value: result,
+        dims: arg.dims,
+    })
+}
+
+/// Writes dimensions in the base units, like `EU/t`
+pub fn unit_name(dims: [i8; 3]) -> String {
+    let part = |(unit, power): (&str, i8)| match power {
+        1 => unit.to_string(),
+        x => format!("{unit}^{x}"),
+    };
+    let above = BASE_UNITS
+        .into_iter()
+        .zip(dims)
+        .filter(|(_, x)| *x > 0)
+        .map(part)
+        .collect::<Vec<_>>();
+    let below = BASE_UNITS
+        .into_iter()
+        .zip(dims)
+        .filter(|(_, x)| *x < 0)
+        .map(|(unit, x)| part((unit, -x)))
+        .collect::<Vec<_>>();
+    match (above.is_empty(), below.is_empty()) {
+        (true, true) => "nothing".to_string(),
+        (false, true) => above.join("·"),
+        (true, false) => format!("1/{}", below.join("·")),
+        (false, false) => format!("{}/{}", above.join("·"), below.join("·")),
+    }
+}
+
+/// Shows a number with commas, or in scientific notation if it is very big or small
+pub fn format_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    if value.abs() >= 1e15 || value.abs() < 1e-4 {
+        return format!("{value:.4e}");
+    }
+
+    let text = format!("{:.4}", value.abs());
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let mut out = String::new();
+    for (i, c) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    if !fraction.is_empty() {
+        out.push('.');
+        out.push_str(fraction);
+    }
+    if value < 0.0 {
+        out.insert(0, '-');
+    }
+    out
+}
+
+/// Shows a quantity in the units that suit it best, with other useful units after
+pub fn describe(quantity: Quantity) -> String {
+    let value = quantity.value;
+    let number = format_number;
+    match quantity.dims {
+        [0, 0, 0] => number(value),
+        [1, 0, 0] => format!("{} EU ({} RF)", number(value), number(value * 4.0)),
+        [1, -1, 0] => {
+            let tier = if value > 0.0 {
+                format!(", {}", TIERS[tier_for(value.ceil() as u64)].0)
+            } else {
+                String::new()
+            };
+            format!(
+                "{} EU/t ({} RF/t{tier})",
+                number(value),
+                number(value * 4.0)
+            )
+        }
+        [0, 1, 0] => format!("{} ticks ({} s)", number(value), number(value / 20.0)),
+        [0, 0, 1] => format!("{} mB ({} B)", number(value), number(value / 1000.0)),
+        [0, -1, 1] => format!("{} mB/t ({} mB/s)", number(value), number(value * 20.0)),
+        dims => format!("{} {}", number(value), unit_name(dims)),
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Quantity(x) => f.write_str(&describe(*x)),
+            Self::Converted(x, unit) => write!(f, "{} {unit}", format_number(*x)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(expr: &str) -> Quantity {
+        match evaluate(expr) {
+            Ok(Answer::Quantity(x)) => x,
+            x => panic!("`{expr}` gave {x:?}"),
+        }
+    }
+
+    fn error(expr: &str) -> CalcError {
+        evaluate(expr).expect_err(expr)
+    }
+
+    #[test]
+    fn tokenises_numbers() {
+        let tokens = tokenise("1_000.5 2e3 .5 2e").unwrap();
+        let toks = tokens.iter().map(|x| x.tok.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            toks,
+            [
+                Tok::Num(1000.5),
+                Tok::Num(2000.0),
+                Tok::Num(0.5),
+                Tok::Num(2.0),
+                Tok::Ident("e".to_string()),
+                Tok::End
+            ]
+        );
+        assert_eq!((tokens[1].start, tokens[1].len), (8, 3));
+    }
+
+    #[test]
+    fn tokenises_units_and_operators() {
+        let toks = tokenise("32EU/t^2")
+            .unwrap()
+            .into_iter()
+            .map(|x| x.tok)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            toks,
+            [
+                Tok::Num(32.0),
+                Tok::Ident("EU".to_string()),
+                Tok::Op('/'),
+                Tok::Ident("t".to_string()),
+                Tok::Op('^'),
+                Tok::Num(2.0),
+                Tok::End
+            ]
+        );
+        assert_eq!(error("1 $ 2").start, 2);
+    }
+
+    #[test]
+    fn follows_precedence() {
+        assert_eq!(quantity("2 + 3 * 4").value, 14.0);
+        assert_eq!(quantity("(2 + 3) * 4").value, 20.0);
+        assert_eq!(quantity("-2^2").value, -4.0);
+        assert_eq!(quantity("2^3^2").value, 512.0);
+        assert_eq!(quantity("2^-1").value, 0.5);
+        assert_eq!(quantity("7 % 4").value, 3.0);
+        assert_eq!(quantity("sqrt(16) + log(100)").value, 6.0);
+    }
+
+    #[test]
+    fn tracks_units() {
+        assert_eq!(quantity("512 EU/t").dims, [1, -1, 0]);
+        // Powers right after a unit only apply to that unit
+        let area = quantity("30 s^2");
+        assert_eq!((area.value, area.dims), (12_000.0, [0, 2, 0]));
+        assert_eq!(quantity("sqrt(16 t^2)").dims, [0, 1, 0]);
+        assert_eq!(quantity("1 B + 5 L").value, 1005.0);
+        assert_eq!(quantity("2 kEU").value, 2000.0);
+        assert_eq!(quantity("1000 RF").value, 250.0);
+    }
+
+    #[test]
+    fn converts() {
+        let convert = |expr| match evaluate(expr) {
+            Ok(Answer::Converted(x, unit)) => (x, unit),
+            x => panic!("`{expr}` gave {x:?}"),
+        };
+        assert_eq!(
+            convert("512 EU/t * 30s in MEU"),
+            (0.3072, "MEU".to_string())
+        );
+        assert_eq!(convert("128 RF/t to EU/t"), (32.0, "EU/t".to_string()));
+        assert_eq!(convert("10 min in ticks"), (12_000.0, "ticks".to_string()));
+        assert_eq!(convert("3 B in mB"), (3000.0, "mB".to_string()));
+    }
+
+    #[test]
+    fn points_at_errors() {
+        let e = error("3 + * 4");
+        assert_eq!((e.start, e.len), (4, 1));
+        assert_eq!(
+            e.format_msg("3 + * 4"),
+            "Invalid expression\n3 + * 4\n    ^ Unexpected `*`"
+        );
+        assert_eq!(error("1 EU + 1 t").start, 5);
+        assert_eq!(error("1 EU in t").start, 8);
+        assert_eq!(error("1/0").start, 1);
+        assert_eq!((error("foo + 1").start, error("foo + 1").len), (0, 3));
+        assert_eq!(error("(1 + 2").start, 0);
+        assert_eq!(error("1 2").start, 2);
+    }
+
+    #[test]
+    fn rejects_huge_powers() {
+        assert!(evaluate("1/(t^-16)^16").is_err());
+        assert!(evaluate("(t^16)^16").is_err());
+        assert!(evaluate("t^64 * t").is_err());
+        assert_eq!(quantity("t^64 / t").dims, [0, 63, 0]);
+        assert!(evaluate(&"(".repeat(100)).is_err());
+    }
+
+    #[test]
+    fn formats_numbers() {
+        assert_eq!(format_number(1234567.0), "1,234,567");
+        assert_eq!(format_number(-1234.5), "-1,234.5");
+        assert_eq!(format_number(0.25), "0.25");
+        assert_eq!(format_number(1e20), "1.0000e20");
+        assert_eq!(describe(quantity("32 EU/t")), "32 EU/t (128 RF/t, LV)");
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::LazyLock,
+};
+
+use stoik::{formula::Molecule, StoikError};
+
+/// The name used for charge when counting elements, it can't clash as elements start uppercase
+pub const CHARGE: &str = "charge";
+
+/// The periodic table, as `symbol,name,mass` with a header line
+const ELEMENTS_CSV: &str = include_str!("../assets/elements.csv");
+
+/// Every element by its symbol, loaded from [`ELEMENTS_CSV`]
+static ELEMENTS: LazyLock<HashMap<&'static str, Element>> = LazyLock::new(|| {
+    ELEMENTS_CSV
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut parts = line.split(',');
+            let symbol = parts.next()?;
+            let name = parts.next()?;
+            let mass = parts.next()?.parse().ok()?;
+            Some((symbol, Element { name, mass }))
+        })
+        .collect()
+});
+
+/// An element from the periodic table
+#[derive(Debug, Clone, Copy)]
+pub struct Element {
+    pub name: &'static str,
+    /// The standard atomic weight in g/mol, or the mass number of the most stable isotope
+    /// for elements without one
+    pub mass: f64,
+}
+
+/// Looks up an element by its symbol, like `Fe`
+pub fn element(symbol: &str) -> Option<Element> {
+    ELEMENTS.get(symbol).copied()
+}
+
+/// A molecule or ion in an equation, like `2SO4^2-`
+#[derive(Debug, Clone)]
+pub struct Species {
+    /// The coefficient written before it, 1 if there wasn't one
+    pub coefficient: i64,
+    /// The formula as written, without the coefficient
+    pub formula: String,
+    /// How many of each element there are, with the charge under [`CHARGE`]
+    pub counts: HashMap<String, i64>,
+}
+
+/// Why an equation couldn't be balanced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceError {
+    /// Only all zero coefficients balance it
+    NoSolution,
+    /// It is a mix of independent reactions, so there is no single answer
+    Infinite,
+    /// It only balances with something on the wrong side
+    WrongSide,
+    /// The numbers got too big to work out
+    TooBig,
+}
+
+impl Species {
+    /// Parses a species, with an optional coefficient before it and charge after it
+    ///
+    /// Charges are written like `^2-`, `^+` or `^3+`, and `e` or `e^-` is an electron.
+    /// Errors are a message to show to the user
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let digits = text.chars().take_while(char::is_ascii_digit).count();
+        let coefficient = match &text[..digits] {
+            "" => 1,
+            x => x
+                .parse()
+                .map_err(|_| format!("`{x}` is too big to be a coefficient"))?,
+        };
+        let formula = text[digits..].trim();
+
+        let (body, charge) = match formula.split_once('^') {
+            Some((body, charge)) => (body, parse_charge(charge)?),
+            None => (formula, 0),
+        };
+
+        let mut counts = if body == "e" {
+            // Electrons have no elements, just charge
+            HashMap::new()
+        } else {
+            Molecule::from_formula(body)
+                .map_err(|e| format!("```{}```", generate_stoik_error_msg(e, body)))?
+                .get_map()
+        };
+        let charge = if body == "e" && charge == 0 {
+            -1
+        } else {
+            charge
+        };
+        if charge != 0 {
+            counts.insert(CHARGE.to_string(), charge);
+        }
+
+        Ok(Self {
+            coefficient,
+            formula: formula.to_string(),
+            counts,
+        })
+    }
+}
+
+/// Parses a charge like `2-`, `+` or `3+`
+fn parse_charge(text: &str) -> Result<i64, String> {
+    let invalid = || format!("`^{text}` is not a valid charge, use something like `^2-` or `^+`");
+    let (amount, sign) = match text.strip_suffix('+') {
+        Some(amount) => (amount, 1),
+        None => (text.strip_suffix('-').ok_or_else(invalid)?, -1),
+    };
+    let amount = match amount {
+        "" => 1,
+        x if x.chars().all(|x| x.is_ascii_digit()) => x.parse::<i64>().map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+    Ok(amount * sign)
+}
+
+/// Adds up the elements and charge on one side, multiplied by their coefficients,
+/// [`None`] if they are too big to add up
+pub fn side_counts(side: &[Species]) -> Option<BTreeMap<String, i64>> {
+    let mut counts = BTreeMap::<String, i64>::new();
+    for species in side {
+        for (element, count) in &species.counts {
+            let total = counts.entry(element.clone()).or_default();
+            *total = total.checked_add(count.checked_mul(species.coefficient)?)?;
+        }
+    }
+    Some(counts)
+}
+
+/// Finds the smallest positive integer coefficients that balance the equation,
+/// reactants then products, ignoring the coefficients already given
+pub fn balance(reactants: &[Species], products: &[Species]) -> Result<Vec<i64>, BalanceError> {
+    let elements = reactants
+        .iter()
+        .chain(products)
+        .flat_map(|x| x.counts.keys())
+        .collect::<BTreeSet<_>>();
+
+    // Each row is an element, and each column a species, products are negative
+    let mut matrix = elements
+        .iter()
+        .map(|element| {
+            let count = |x: &Species| i128::from(x.counts.get(*element).copied().unwrap_or(0));
+            reactants
+                .iter()
+                .map(count)
+                .chain(products.iter().map(|x| -count(x)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let columns = reactants.len() + products.len();
+
+    let pivots = reduce(&mut matrix, columns).ok_or(BalanceError::TooBig)?;
+    let free = (0..columns)
+        .filter(|x| !pivots.iter().any(|(_, col)| col == x))
+        .collect::<Vec<_>>();
+    let free = match free[..] {
+        [] => return Err(BalanceError::NoSolution),
+        [free] => free,
+        _ => return Err(BalanceError::Infinite),
+    };
+
+    // Each pivot row reads `a * x_pivot + b * x_free = 0`, so scale x_free by every `a`
+    let scale = pivots
+        .iter()
+        .try_fold(1, |acc, (row, col)| lcm(acc, matrix[*row][*col].abs()))
+        .ok_or(BalanceError::TooBig)?;
+    let mut solution = vec![0; columns];
+    solution[free] = scale;
+    for (row, col) in &pivots {
+        solution[*col] = matrix[*row][free]
+            .checked_mul(scale)
+            .and_then(i128::checked_neg)
+            .ok_or(BalanceError::TooBig)?
+            / matrix[*row][*col];
+    }
+
+    // The free value is positive, so anything else that isn't is on the wrong side
+    if solution.iter().any(|x| *x <= 0) {
+        return Err(BalanceError::WrongSide);
+    }
+    let divisor = solution.iter().fold(0, |acc, x| gcd(acc, *x));
+    solution
+        .into_iter()
+        .map(|x| i64::try_from(x / divisor).map_err(|_| BalanceError::TooBig))
+        .collect()
+}
+
+/// Reduces the matrix in place using only integers, so every pivot is the only non zero
+/// value in its column, returning the row and column of each pivot
+///
+/// The values grow with each step, so this is [`None`] if they get too big
+fn reduce(matrix: &mut [Vec<i128>], columns: usize) -> Option<Vec<(usize, usize)>> {
+    let mut pivots = Vec::new();
+    let mut row = 0;
+    for col in 0..columns {
+        let Some(found) = (row..matrix.len()).find(|x| matrix[*x][col] != 0) else {
+            continue;
+        };
+        matrix.swap(row, found);
+
+        let pivot_row = matrix[row].clone();
+        for (i, other) in matrix.iter_mut().enumerate() {
+            if i == row || other[col] == 0 {
+                continue;
+            }
+            let factor = other[col];
+            for (x, pivot) in other.iter_mut().zip(&pivot_row) {
+                *x = x
+                    .checked_mul(pivot_row[col])?
+                    .checked_sub(pivot.checked_mul(factor)?)
+                    // Keeps every value safe to take the absolute value of
+                    .filter(|x| *x != i128::MIN)?;
+            }
+            // Keep the numbers small
+            let divisor = other.iter().fold(0, |acc, x| gcd(acc, *x));
+            if divisor > 1 {
+                other.iter_mut().for_each(|x| *x /= divisor);
+            }
+        }
+
+        pivots.push((row, col));
+        row += 1;
+    }
+    Some(pivots)
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn lcm(a: i128, b: i128) -> Option<i128> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// Writes out one side of an equation with the given coefficients, leaving out 1s
+pub fn format_side(side: &[Species], coefficients: &[i64]) -> String {
+    side.iter()
+        .zip(coefficients)
+        .map(|(species, coefficient)| match coefficient {
+            1 => species.formula.clone(),
+            x => format!("{x}{}", species.formula),
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// Makes a message pointing to what is wrong with a formula
+// maybe move to a pub func in stoik itself?
+pub fn generate_stoik_error_msg(e: StoikError, formula: &str) -> String {
+    match e {
+        StoikError::InvalidToken(loc) => {
+            loc.format_msg(formula, "Malformed formula", "Illegal token")
+        }
+        StoikError::NumberFirst(loc) => loc.format_msg(
+            formula,
+            "Malformed formula",
+            "Compound groups cannot start with numbers",
+        ),
+        StoikError::UnpairedParenthesis(loc) => {
+            loc.format_msg(formula, "Malformed formula", "Unpaired parenthesis")
+        }
+        StoikError::UnpairedBracket(loc) => {
+            loc.format_msg(formula, "Malformed formula", "Unpaired bracket")
+        }
+        e => e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn side(text: &str) -> Vec<Species> {
+        text.split(" + ")
+            .map(|x| Species::parse(x).unwrap())
+            .collect()
+    }
+
+    fn balanced(equation: &str) -> Result<Vec<i64>, BalanceError> {
+        let (reactants, products) = equation.split_once(" -> ").unwrap();
+        balance(&side(reactants), &side(products))
+    }
+
+    #[test]
+    fn parses_species() {
+        let species = Species::parse("2SO4^2-").unwrap();
+        assert_eq!(species.coefficient, 2);
+        assert_eq!(species.formula, "SO4^2-");
+        assert_eq!(species.counts["S"], 1);
+        assert_eq!(species.counts["O"], 4);
+        assert_eq!(species.counts[CHARGE], -2);
+
+        assert_eq!(Species::parse("e").unwrap().counts[CHARGE], -1);
+        assert_eq!(Species::parse("e^-").unwrap().counts[CHARGE], -1);
+        assert!(Species::parse("Fe^2").is_err());
+        assert!(Species::parse("Fe^-2-").is_err());
+    }
+
+    #[test]
+    fn balances_simple_reactions() {
+        assert_eq!(balanced("H2 + O2 -> H2O"), Ok(vec![2, 1, 2]));
+        assert_eq!(balanced("CH4 + O2 -> CO2 + H2O"), Ok(vec![1, 2, 1, 2]));
+        // Coefficients that are given are ignored
+        assert_eq!(balanced("5Fe + 7O2 -> Fe2O3"), Ok(vec![4, 3, 2]));
+    }
+
+    #[test]
+    fn balances_redox() {
+        assert_eq!(
+            balanced("MnO4^- + Fe^2+ + H^+ -> Mn^2+ + Fe^3+ + H2O"),
+            Ok(vec![1, 5, 8, 1, 5, 4])
+        );
+        assert_eq!(balanced("Cu^2+ + e^- -> Cu"), Ok(vec![1, 2, 1]));
+        assert_eq!(balanced("Fe^3+ + e -> Fe^2+"), Ok(vec![1, 1, 1]));
+    }
+
+    #[test]
+    fn explains_what_cant_be_balanced() {
+        assert_eq!(balanced("H2 -> O2"), Err(BalanceError::NoSolution));
+        assert_eq!(
+            balanced("H2 + O2 -> H2O + H2O2"),
+            Err(BalanceError::Infinite)
+        );
+        assert_eq!(balanced("H2O + H2 -> O2"), Err(BalanceError::WrongSide));
+    }
+
+    #[test]
+    fn catches_overflow() {
+        let big = side("9999999999999C999999");
+        assert!(side_counts(&big).is_none());
+        assert!(side_counts(&side("2H2O + 3H2")).is_some_and(|x| x["H"] == 10));
+
+        let huge = "C4611686018427387903H4611686018427387902";
+        assert_eq!(
+            balanced(&format!(
+                "{huge} + H4611686018427387901 -> C3H4611686018427387899"
+            )),
+            Err(BalanceError::TooBig)
+        );
+    }
+}
@@ -1,10 +1,9 @@
-use tracing::error;
-
 use crate::command::impls::*;
 
-use super::ExtendedCommand;
-
-const ERROR_MSG: &[u8] = "⚠️**ERROR**⚠️ Broken Command".as_bytes();
+use super::{
+    plugin::{plugin_exists, plugin_names, PluginCommand, PLUGIN_PREFIX},
+    CommandMeta, ExtendedCommand,
+};
 
 macro_rules! generate_array {
     ($first:tt, $($next:tt),+) => {
@@ -17,8 +16,37 @@ macro_rules! generate_array {
 
 macro_rules! generate_construct {
     ($($cmd:ty),+) => {
-        /// All the valid command names
-        pub const VALID: [&str; generate_array!($($cmd),+)] = [$(stringify!($cmd)),+];
+        /// All the valid command type ids
+        pub const VALID: [&str; generate_array!($($cmd),+)] = [$(<$cmd as CommandMeta>::ID),+];
+        /// Checks if a type can be used for new commands, including loaded plugins
+        pub fn is_valid(ty: &str) -> bool {
+            VALID.contains(&ty) || plugin_exists(ty)
+        }
+        /// Gets the information on every type that can be used for new commands
+        pub fn types() -> Vec<TypeInfo> {
+            let mut types = vec![$(
+                TypeInfo {
+                    id: <$cmd as CommandMeta>::ID.to_owned(),
+                    description: <$cmd as CommandMeta>::DESCRIPTION,
+                    schema: Some(<$cmd as CommandMeta>::schema()),
+                }
+            ),+];
+            types.extend(plugin_names().into_iter().map(|name| TypeInfo {
+                id: format!("{PLUGIN_PREFIX}{name}"),
+                description: "A command provided by a WASM plugin",
+                schema: None,
+            }));
+            types
+        }
+        /// Gets the schema for the data of a command type, if it has one
+        pub fn schema(ty: &str) -> Option<serde_json::Value> {
+            match ty {
+                $(
+                    x if x == <$cmd as CommandMeta>::ID => Some(<$cmd as CommandMeta>::schema()),
+                )+
+                _ => None,
+            }
+        }
         /// Construct a command from a type, binary data and cli arguments
         pub fn construct(
             ty: &str,
@@ -27,22 +55,47 @@ macro_rules! generate_construct {
         ) -> color_eyre::Result<Box<dyn ExtendedCommand + Send + Sync>> {
             Ok(match ty {
                 $(
-                    stringify!($cmd) => {
+                    x if x == <$cmd as CommandMeta>::ID => {
                         Box::new(<$cmd as ExtendedCommand>::construct(&cli, data)?) as Box<dyn ExtendedCommand + Send + Sync>
                     },
                 )+
-                _ => {
-                    let msg = format!("{ty} is not a valid command type");
-                    error!("{msg}");
-                    Box::new(MessageCommand::construct(&cli, ERROR_MSG)?) as Box<dyn ExtendedCommand + Send + Sync>
+                // Plugins that aren't loaded still keep their data
+                _ if ty.starts_with(PLUGIN_PREFIX) => {
+                    Box::new(PluginCommand::new(ty, data)?) as Box<dyn ExtendedCommand + Send + Sync>
                 }
+                _ => color_eyre::eyre::bail!("`{ty}` is not a valid command type"),
             })
         }
     };
 }
 
-// TODO: maybe allow for dynamially loaded plugins to register plugins
-// also maybe name commands better
+#[derive(Debug, Clone)]
+/// Information on a command type, for people adding commands
+pub struct TypeInfo {
+    /// The id the type is stored and added with
+    pub id: String,
+    pub description: &'static str,
+    /// The json schema for the type's data, [`None`] if any data is allowed
+    pub schema: Option<serde_json::Value>,
+}
+
+/// Checks json data from a user against the schema of a command type
+///
+/// Empty json is checked as `null`
+pub fn validate_data(ty: &str, json: &str) -> Result<(), String> {
+    let Some(schema) = schema(ty) else {
+        return Ok(());
+    };
+    let value = if json.trim().is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_str(json)
+            .map_err(|e| format!("⚠️ Error deseralising json data: ```{e}```"))?
+    };
+    super::schema::validate(&schema, &value).map_err(|e| format!("⚠️ Invalid data for `{ty}`: {e}"))
+}
+
+// TODO: maybe name commands better
 generate_construct!(
     MessageCommand,
     AddCommand,
@@ -54,5 +107,23 @@ generate_construct!(
     VersionCommand,
     FlopCountCommand,
     WikiCommand,
-    StoikCommand
+    StoikCommand,
+    MolarMassCommand,
+    RawCommand,
+    AttachmentCommand,
+    ListCommand,
+    ChoiceCommand,
+    ScriptCommand,
+    PluginsCommand,
+    TypesCommand,
+    BrokenCommand,
+    ShutdownCommand,
+    ScheduleCommand,
+    RemindCommand,
+    RespondersCommand,
+    GregTechCommand,
+    OverclockCommand,
+    ApiCommand,
+    GithubCommand,
+    CalcCommand
 );
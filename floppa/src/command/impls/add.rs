@@ -1,19 +1,29 @@
+use std::sync::Arc;
+
 use serenity::{all::Mentionable, async_trait, model::channel::Message};
 use tokio::sync::RwLock;
 
 use crate::{
-    command::{check_name, construct, inner::CmdCtx, ExtendedCommand, FlopMessagable, VALID},
+    command::{
+        check_name, construct, inner::CmdCtx, is_valid, json_to_data, validate_data, CommandMeta,
+        ExtendedCommand, FlopMessagable,
+    },
     sql::{CmdNode, FlopDB, FlopRole},
     Cli, FlopResult,
 };
 
-use super::MessageCommand;
+use super::{AttachmentCommand, MessageCommand};
 
 #[derive(Debug)]
 pub struct AddCommand {
     cli: Cli,
 }
 
+impl CommandMeta for AddCommand {
+    const ID: &'static str = "AddCommand";
+    const DESCRIPTION: &'static str = "Adds a new command";
+}
+
 #[async_trait]
 impl ExtendedCommand for AddCommand {
     fn construct(cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
@@ -21,17 +31,22 @@ impl ExtendedCommand for AddCommand {
     }
 
     async fn execute<'b>(
-        &mut self,
+        &self,
         msg: &Message,
         ctx: CmdCtx<'b>,
         db: &RwLock<FlopDB>,
     ) -> FlopResult<FlopMessagable> {
         let args = msg.content.trim_start_matches(ctx.command).trim();
-        let Some((name, body)) = args.split_once(char::is_whitespace) else {
-            return Ok(FlopMessagable::Text(format!(
-                "Usage: `{0} (name) (body)`\nor`{0} (name) (--[type]) [json data]`",
-                ctx.command
-            )));
+        let (name, body) = match args.split_once(char::is_whitespace) {
+            Some(split) => split,
+            // Commands with files dont need a body
+            None if !args.is_empty() && !msg.attachments.is_empty() => (args, ""),
+            None => {
+                return Ok(FlopMessagable::Text(format!(
+                    "Usage: `{0} (name) (body)`\nor`{0} (name) (--[type]) [json data]`\nAttach files to have them sent by the command",
+                    ctx.command
+                )))
+            }
         };
 
         // check invalid names
@@ -55,39 +70,24 @@ impl ExtendedCommand for AddCommand {
             if !lock.user_has_role(msg.author.id, &FlopRole::RegMod(ctx.registry.to_owned())) {
                 return Ok(FlopMessagable::Text(":clueless:".to_string()));
             }
-            let (ty, body) = match body.split_once(char::is_whitespace) {
-                Some((ty, body)) => (&ty.trim()[..(ty.len() - 2)][3..], body),
-                None => (&body.trim()[..(body.len() - 1)][3..], ""),
-            };
-
-            // deal with json data
-            let mut data: Vec<u8> = vec![];
-            if !body.is_empty() {
-                let value = match serde_json::from_str::<rmpv::Value>(body) {
-                    Ok(val) => val,
-                    Err(e) => {
-                        return Ok(FlopMessagable::Text(format!(
-                            "⚠️ Error deseralising json data: ```{e}```"
-                        )))
-                    }
-                };
-
-                data = match rmp_serde::to_vec(&value) {
-                    Ok(val) => val,
-                    Err(e) => {
-                        return Ok(FlopMessagable::Text(format!(
-                            "⚠️ Error seralising msgpack data: ```{e}```"
-                        )))
-                    }
-                };
-            }
+            let (ty, body) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+            let ty = ty.trim_start_matches("--[").trim_end_matches(']');
 
-            if !VALID.contains(&ty) {
+            if !is_valid(ty) {
                 return Ok(FlopMessagable::Text(format!(
-                    "⚠️ `{ty}` is not a valid command type"
+                    "⚠️ `{ty}` is not a valid command type, see the `types` command"
                 )));
             }
 
+            // deal with json data
+            if let Err(e) = validate_data(ty, body) {
+                return Ok(FlopMessagable::Text(e));
+            }
+            let data = match json_to_data(body) {
+                Ok(data) => data,
+                Err(e) => return Ok(FlopMessagable::Text(e)),
+            };
+
             let cmd = match construct(ty, &data, &self.cli) {
                 Ok(cmd) => cmd,
                 Err(e) => {
@@ -109,6 +109,43 @@ impl ExtendedCommand for AddCommand {
                 return Ok(FlopMessagable::Text(":clueless:".to_string()));
             }
 
+            if !msg.attachments.is_empty() {
+                // Dont hold the db while downloading the files
+                drop(lock);
+                let cmd = match AttachmentCommand::from_message(
+                    &self.cli,
+                    &ctx.cfg.attachments,
+                    msg,
+                    body,
+                )
+                .await?
+                {
+                    Ok(cmd) => cmd,
+                    Err(e) => return Ok(FlopMessagable::Text(e)),
+                };
+
+                lock = db.write().await;
+                if lock.command_exists(ctx.registry.to_owned(), name) {
+                    drop(lock);
+                    if let Some(data) = ExtendedCommand::save(&cmd) {
+                        AttachmentCommand::remove_files(&self.cli, &data, db).await;
+                    }
+                    return Ok(FlopMessagable::Text(format!(
+                        "⚠️ `{name}` was added while downloading the files"
+                    )));
+                }
+                lock.add_command(
+                    ctx.registry.to_owned(),
+                    name.to_string(),
+                    &msg.author,
+                    AttachmentCommand::ID.to_owned(),
+                    CmdNode::Cmd(Arc::new(cmd)),
+                );
+                drop(lock);
+
+                return Ok(FlopMessagable::Text(format!("Added command `{name}`")));
+            }
+
             if body.is_empty() {
                 return Ok(FlopMessagable::Text(
                     "Command body cannot be empty".to_string(),
@@ -127,8 +164,8 @@ impl ExtendedCommand for AddCommand {
                 ctx.registry.to_owned(),
                 name.to_string(),
                 &msg.author,
-                "MessageCommand".to_owned(),
-                CmdNode::Cmd(Box::new(cmd)),
+                MessageCommand::ID.to_owned(),
+                CmdNode::Cmd(Arc::new(cmd)),
             );
         }
 
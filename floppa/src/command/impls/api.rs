@@ -0,0 +1,219 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serenity::{async_trait, model::prelude::Message};
+use tracing::error;
+
+use crate::{
+    command::{inner::CmdCtx, Command, CommandMeta, FlopMessagable},
+    http::host_allowed,
+    stuff::truncate,
+    Cli, FlopResult,
+};
+
+/// The max length of a response, so it fits in a message
+const OUTPUT_LIMIT: usize = 2000;
+
+#[derive(Debug)]
+pub struct ApiCommand {
+    data: ApiData,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiData {
+    /// The url to request, see [`render`] for the placeholders
+    url: String,
+    /// A json pointer to the part of the response to use, like `/0/tag_name`
+    #[serde(default)]
+    pointer: String,
+    /// What to respond with, see [`render`] for the placeholders
+    #[serde(default = "default_template")]
+    template: String,
+}
+
+fn default_template() -> String {
+    "{value}".to_string()
+}
+
+impl CommandMeta for ApiCommand {
+    const ID: &'static str = "ApiCommand";
+    const DESCRIPTION: &'static str =
+        "Requests json from an allowed host and responds with parts of it, using templates";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string", "minLength": 1 },
+                "pointer": { "type": "string" },
+                "template": { "type": "string", "minLength": 1 }
+            },
+            "required": ["url"]
+        })
+    }
+}
+
+#[async_trait]
+impl Command for ApiCommand {
+    fn construct(_cli: &Cli, data: &[u8]) -> FlopResult<Self> {
+        let data: ApiData = rmp_serde::from_slice(data)?;
+        if !data.pointer.is_empty() && !data.pointer.starts_with('/') {
+            color_eyre::eyre::bail!("The pointer must be empty or start with `/`");
+        }
+        // Check the url is valid, with something in every placeholder
+        let example = render(&data.url, &[], None, true, true)
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid url: {e}"))?;
+        let url = Url::parse(&example)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            color_eyre::eyre::bail!("The url must be http or https");
+        }
+        Ok(Self { data })
+    }
+
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let args = msg
+            .content
+            .trim_start_matches(ctx.command)
+            .split_whitespace()
+            .collect::<Vec<_>>();
+
+        let url = match render(&self.data.url, &args, None, true, false) {
+            Ok(url) => url,
+            Err(e) => return Ok(FlopMessagable::Text(format!("⚠️ {e}"))),
+        };
+        let url = match Url::parse(&url) {
+            Ok(url) => url,
+            Err(e) => return Ok(FlopMessagable::Text(format!("⚠️ Invalid url: `{e}`"))),
+        };
+        // Checked here as hosts can be changed in the config after the command is added
+        let host = url.host_str().unwrap_or_default().to_owned();
+        if !host_allowed(&ctx.cfg.http.api_hosts, &host) {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{host}` is not an allowed host"
+            )));
+        }
+
+        let response = match ctx.http.get(url).await {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Error requesting `{host}`: {e}");
+                return Ok(FlopMessagable::Text(format!(
+                    "⚠️ Error requesting `{host}`: `{e}`"
+                )));
+            }
+        };
+        if !response.status.is_success() {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{host}` responded with {}",
+                response.status
+            )));
+        }
+        let json = match serde_json::from_slice::<Value>(&response.body) {
+            Ok(json) => json,
+            Err(e) => {
+                return Ok(FlopMessagable::Text(format!(
+                    "⚠️ `{host}` didn't respond with json: `{e}`"
+                )))
+            }
+        };
+        let Some(value) = json.pointer(&self.data.pointer) else {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ The response has nothing at `{}`",
+                self.data.pointer
+            )));
+        };
+
+        let text = match render(&self.data.template, &args, Some(value), false, false) {
+            Ok(text) => text,
+            Err(e) => format!("⚠️ {e}"),
+        };
+        Ok(FlopMessagable::Text(truncate(&text, OUTPUT_LIMIT)))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        match rmp_serde::to_vec_named(&self.data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Error serialising api command: `{e}`");
+                None
+            }
+        }
+    }
+}
+
+/// Fills in the placeholders in a template, errors are a message for the user
+///
+/// - `{0}`, `{1}`, ... are the arguments the command was ran with
+/// - `{args}` is all of the arguments
+/// - `{value}` is the part of the response at the pointer
+/// - `{/path}` is a json pointer into that part of the response
+///
+/// Arguments are percent encoded if `encode`, and placeholders are filled with `x` if `example`
+fn render(
+    template: &str,
+    args: &[&str],
+    value: Option<&Value>,
+    encode: bool,
+    example: bool,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let key = &rest[start + 1..start + len];
+        rest = &rest[start + len + 1..];
+
+        let arg = |text: &str| {
+            if encode {
+                url_encode(text)
+            } else {
+                text.to_owned()
+            }
+        };
+        let filled = match key {
+            _ if example => "x".to_string(),
+            "args" => arg(&args.join(" ")),
+            "value" => value.map(json_text).unwrap_or_default(),
+            x if x.starts_with('/') => match value.and_then(|v| v.pointer(x)) {
+                Some(found) => json_text(found),
+                None => return Err(format!("The response has nothing at `{x}`")),
+            },
+            x => match x.parse::<usize>() {
+                Ok(i) => match args.get(i) {
+                    Some(found) => arg(found),
+                    None => return Err(format!("This command needs at least {} arguments", i + 1)),
+                },
+                // Not a placeholder, so keep it as it was
+                Err(_) => format!("{{{x}}}"),
+            },
+        };
+        out.push_str(&filled);
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Shows json as text, strings without their quotes
+fn json_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        x => x.to_string(),
+    }
+}
+
+/// Percent encodes text to go in a url
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|x| match x {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (x as char).to_string()
+            }
+            x => format!("%{x:02X}"),
+        })
+        .collect()
+}
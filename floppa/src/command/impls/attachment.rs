@@ -0,0 +1,252 @@
+use std::{
+    collections::HashSet,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{CreateAttachment, Message},
+    async_trait,
+};
+use tokio::{fs, sync::RwLock};
+use tracing::error;
+
+use crate::{
+    command::{inner::CmdCtx, Command, CommandMeta, FlopMessagable},
+    config::AttachmentConfig,
+    sql::FlopDB,
+    Cli, FlopResult,
+};
+
+use super::{choice, ChoiceCommand};
+
+/// The folder in the run dir that stored files are kept in
+pub(super) const ATTACHMENT_DIR: &str = "attachments";
+
+#[derive(Debug)]
+pub struct AttachmentCommand {
+    dir: PathBuf,
+    data: AttachmentData,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AttachmentData {
+    /// Text to send along with the files
+    text: Option<String>,
+    /// Paths of the files, relative to [`ATTACHMENT_DIR`]
+    files: Vec<String>,
+}
+
+impl AttachmentCommand {
+    /// Creates the command from the attachments on a message, storing them under the run dir
+    ///
+    /// The outer result is for io errors, the inner one is for messages for the user
+    pub async fn from_message(
+        cli: &Cli,
+        cfg: &AttachmentConfig,
+        msg: &Message,
+        text: &str,
+    ) -> FlopResult<Result<Self, String>> {
+        if msg.attachments.len() > cfg.max_count {
+            return Ok(Err(format!(
+                "⚠️ Commands can only have {} attachments",
+                cfg.max_count
+            )));
+        }
+        if let Some(file) = msg.attachments.iter().find(|x| x.size > cfg.max_size) {
+            return Ok(Err(format!(
+                "⚠️ `{}` is larger than the limit of {} bytes",
+                file.filename, cfg.max_size
+            )));
+        }
+
+        let dir = cli.get_path(ATTACHMENT_DIR);
+        let mut files = Vec::with_capacity(msg.attachments.len());
+        for attachment in &msg.attachments {
+            // Keep the original name so it shows up the same when sent
+            let name = attachment.filename.replace(['/', '\\'], "_");
+            let path = format!("{}/{name}", attachment.id);
+            files.push(path.clone());
+
+            let stored = async {
+                let data = attachment.download().await?;
+                fs::create_dir_all(dir.join(attachment.id.to_string())).await?;
+                fs::write(dir.join(&path), data).await?;
+                FlopResult::Ok(())
+            };
+            if let Err(e) = stored.await {
+                // Don't leave the files that were already stored behind
+                delete_files(&dir, &files).await;
+                return Err(e);
+            }
+        }
+
+        let text = text.trim();
+        Ok(Ok(Self {
+            dir,
+            data: AttachmentData {
+                text: (!text.is_empty()).then(|| text.to_owned()),
+                files,
+            },
+        }))
+    }
+
+    /// Deletes the files stored for a command that no other command uses, from its saved data
+    ///
+    /// Used when the command is removed, or edited into something else, so it must already be
+    /// out of the db or replaced in it, and no locks on the db or its commands can be held
+    pub async fn remove_files(cli: &Cli, data: &[u8], db: &RwLock<FlopDB>) {
+        let Ok(data) = rmp_serde::from_slice::<AttachmentData>(data) else {
+            return;
+        };
+        // Held until the files are gone, so nothing can start using them in the meantime
+        let db_lock = db.read().await;
+        let used = used_files(&db_lock).await;
+        let unused = data
+            .files
+            .into_iter()
+            .filter(|x| !used.contains(Path::new(x)))
+            .collect::<Vec<_>>();
+        delete_files(&cli.get_path(ATTACHMENT_DIR), &unused).await;
+        drop(db_lock);
+    }
+}
+
+/// Gets every stored file that a command points at
+///
+/// Files can be shared, by copying the data of an attachment command or from choice responses
+async fn used_files(db: &FlopDB) -> HashSet<PathBuf> {
+    let mut used = HashSet::new();
+    for data in db.command_data(AttachmentCommand::ID).await {
+        if let Ok(data) = rmp_serde::from_slice::<AttachmentData>(&data) {
+            used.extend(data.files.into_iter().map(PathBuf::from));
+        }
+    }
+    for data in db.command_data(ChoiceCommand::ID).await {
+        used.extend(choice::stored_files(&data).into_iter().map(PathBuf::from));
+    }
+    used
+}
+
+/// Deletes stored files, along with any of their folders that are left empty
+async fn delete_files(dir: &Path, paths: &[String]) {
+    for path in paths {
+        let Ok(path) = checked_path(dir, path) else {
+            continue;
+        };
+        if let Err(e) = fs::remove_file(&path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                error!(
+                    "Error deleting stored attachment `{}`: `{e}`",
+                    path.display()
+                );
+            }
+        }
+        // Each attachment has its own folder, which is only removed once nothing is in it
+        for parent in path.ancestors().skip(1).take_while(|x| *x != dir) {
+            if fs::remove_dir(parent).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Reads stored files to send, logging and skipping any that can't be read
+pub(super) async fn read_files(dir: &Path, paths: &[String]) -> Vec<CreateAttachment> {
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        // Checked again in case the folder has changed since the command was loaded
+        let data = match checked_path(dir, path) {
+            Ok(full) => fs::read(full).await.map_err(Into::into),
+            Err(e) => Err(e),
+        };
+        match data {
+            Ok(data) => files.push(CreateAttachment::bytes(data, name)),
+            Err(e) => error!("Error reading stored attachment `{path}`: `{e}`"),
+        }
+    }
+    files
+}
+
+/// Resolves a stored file's path, making sure it is inside `dir`
+pub(super) fn checked_path(dir: &Path, path: &str) -> FlopResult<PathBuf> {
+    let relative = Path::new(path);
+    if relative.as_os_str().is_empty()
+        || !relative
+            .components()
+            .all(|x| matches!(x, Component::Normal(_)))
+    {
+        color_eyre::eyre::bail!("`{path}` must be a relative path inside the attachment folder");
+    }
+
+    let full = dir.join(relative);
+    // Files that are missing are allowed, they are just skipped when sending
+    match (std::fs::canonicalize(dir), std::fs::canonicalize(&full)) {
+        (Ok(dir), Ok(resolved)) if !resolved.starts_with(&dir) => {
+            color_eyre::eyre::bail!("`{path}` is outside the attachment folder")
+        }
+        _ => Ok(full),
+    }
+}
+
+impl CommandMeta for AttachmentCommand {
+    const ID: &'static str = "AttachmentCommand";
+    const DESCRIPTION: &'static str = "Sends stored files, added by attaching them to `add`";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": ["string", "null"] },
+                "files": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["files"]
+        })
+    }
+}
+
+#[async_trait]
+impl Command for AttachmentCommand {
+    fn construct(cli: &Cli, data: &[u8]) -> FlopResult<Self> {
+        let data = if data.is_empty() {
+            AttachmentData::default()
+        } else {
+            rmp_serde::from_slice::<AttachmentData>(data)?
+        };
+        let dir = cli.get_path(ATTACHMENT_DIR);
+        for path in &data.files {
+            checked_path(&dir, path)?;
+        }
+        Ok(Self { dir, data })
+    }
+
+    async fn execute<'a>(&self, _msg: &Message, _ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let files = read_files(&self.dir, &self.data.files).await;
+
+        let mut out = Vec::with_capacity(2);
+        if let Some(text) = &self.data.text {
+            out.push(FlopMessagable::Text(text.clone()));
+        }
+        if !files.is_empty() {
+            out.push(FlopMessagable::Attachments(files));
+        }
+        if out.is_empty() {
+            out.push(FlopMessagable::Text(
+                "⚠️ This command's files are missing".to_owned(),
+            ));
+        }
+
+        Ok(FlopMessagable::Combined(out))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        match rmp_serde::to_vec_named(&self.data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Error serialising attachment command: `{e}`");
+                None
+            }
+        }
+    }
+}
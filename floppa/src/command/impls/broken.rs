@@ -0,0 +1,104 @@
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter},
+    async_trait,
+    model::channel::Message,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    sql::{FlopDB, FlopRole},
+    stuff::truncate,
+    Cli, FlopResult,
+};
+
+/// How many broken commands are shown on each page
+const PER_PAGE: usize = 10;
+/// The max length of an error shown in an embed field
+const ERROR_LIMIT: usize = 900;
+
+#[derive(Debug)]
+pub struct BrokenCommand {
+    cli: Cli,
+}
+
+impl CommandMeta for BrokenCommand {
+    const ID: &'static str = "BrokenCommand";
+    const DESCRIPTION: &'static str = "Lists or retries loading the commands that failed to load";
+}
+
+#[async_trait]
+impl ExtendedCommand for BrokenCommand {
+    fn construct(cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self { cli: cli.clone() })
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command).trim();
+        let db_lock = db.read().await;
+        if !db_lock.user_has_role(msg.author.id, &FlopRole::Admin) {
+            return Ok(FlopMessagable::Text(":clueless:".to_string()));
+        }
+
+        match args {
+            "" => {}
+            "retry" => {
+                let fixed = db_lock.retry_broken(&self.cli).await;
+                let left = db_lock.broken_commands().await.len();
+                return Ok(FlopMessagable::Text(format!(
+                    "Fixed {fixed} commands, {left} are still broken"
+                )));
+            }
+            _ => {
+                return Ok(FlopMessagable::Text(format!(
+                    "Usage: `{0}` to list broken commands or `{0} retry` to try loading them again",
+                    ctx.command
+                )))
+            }
+        }
+
+        let broken = db_lock.broken_commands().await;
+        drop(db_lock);
+
+        if broken.is_empty() {
+            return Ok(FlopMessagable::Text("No commands are broken".to_string()));
+        }
+
+        let count = broken.len().div_ceil(PER_PAGE);
+        let pages = broken
+            .chunks(PER_PAGE)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let fields = chunk.iter().map(|x| {
+                    // Fences in the error would end its block early
+                    let error = truncate(&x.error.replace("```", "`\u{200b}``"), ERROR_LIMIT);
+                    (
+                        format!("{}:{}", x.registry, x.name),
+                        format!("`{}`\n```{error}```", x.ty),
+                        false,
+                    )
+                });
+                CreateEmbed::new()
+                    .title("Broken commands")
+                    .fields(fields)
+                    .footer(CreateEmbedFooter::new(format!(
+                        "Page {}/{count}, {} commands",
+                        i + 1,
+                        broken.len()
+                    )))
+                    .into()
+            })
+            .collect();
+
+        Ok(FlopMessagable::Pages(pages))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
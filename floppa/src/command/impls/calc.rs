@@ -0,0 +1,48 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{
+    calc::evaluate,
+    command::{CmdCtx, Command, CommandMeta, FlopMessagable},
+    Cli, FlopResult,
+};
+
+const HELP_MSG: &str = "Usage: calc [FLAGS] ... [EXPRESSION] [in UNITS]
+Works out EXPRESSION, which can have units, optionally converting the answer into UNITS
+  -h, --help Shows this
+
+Operators: + - * / % ^ ( )
+Functions: sqrt abs floor ceil round ln log
+Units: EU RF FE, t s min h, mB L B, with SI prefixes like kEU or ms
+Example: calc 512 EU/t * 30s in MEU";
+
+#[derive(Debug)]
+pub struct CalcCommand;
+
+impl CommandMeta for CalcCommand {
+    const ID: &'static str = "CalcCommand";
+    const DESCRIPTION: &'static str =
+        "Works out arithmetic with units like EU, RF, ticks and buckets, converting between them";
+}
+
+#[async_trait]
+impl Command for CalcCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let expr = msg.content.trim_start_matches(ctx.command).trim();
+        if expr.is_empty() || expr.split_whitespace().any(|x| x == "--help" || x == "-h") {
+            return Ok(FlopMessagable::Text(HELP_MSG.to_string()));
+        }
+
+        Ok(FlopMessagable::Text(match evaluate(expr) {
+            Ok(answer) => format!("`{expr}` = **{answer}**"),
+            Err(e) => format!("```{}```", e.format_msg(expr)),
+        }))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
@@ -0,0 +1,248 @@
+use std::{path::PathBuf, sync::Mutex};
+
+use rand::{distributions::WeightedIndex, prelude::Distribution};
+use serde::{Deserialize, Serialize};
+use serenity::{all::CreateEmbed, async_trait, model::prelude::Message};
+use tracing::error;
+
+use crate::{
+    command::{inner::CmdCtx, Command, CommandMeta, FlopMessagable},
+    Cli, FlopResult,
+};
+
+use super::attachment::{checked_path, read_files, ATTACHMENT_DIR};
+
+#[derive(Debug)]
+pub struct ChoiceCommand {
+    /// Locked as the position changes when rotating
+    data: Mutex<ChoiceData>,
+    /// The sum of all the weights
+    total: u32,
+    /// Where the files responses attach are stored
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChoiceData {
+    /// How the response is picked
+    #[serde(default)]
+    mode: ChoiceMode,
+    /// All the responses that can be picked
+    responses: Vec<ChoiceResponse>,
+    /// How far through the rotation the command is, in weight
+    #[serde(default)]
+    position: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ChoiceMode {
+    /// Pick a response at random, more likely for higher weights
+    #[default]
+    Random,
+    /// Go through the responses in order, repeating each one its weight in times
+    Rotate,
+}
+
+/// A response, which is sent as one message with any of text, an embed and files
+#[derive(Debug, Serialize, Deserialize)]
+struct ChoiceResponse {
+    /// The text to respond with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embed: Option<ChoiceEmbed>,
+    /// Files to attach, stored the same way as for attachment commands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    files: Vec<String>,
+    /// How likely this response is, compared to the others
+    #[serde(default = "default_weight")]
+    weight: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChoiceEmbed {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    /// The url of an image to show in the embed
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    colour: Option<u32>,
+}
+
+impl ChoiceEmbed {
+    fn build(&self) -> CreateEmbed {
+        let mut embed = CreateEmbed::new();
+        if let Some(title) = &self.title {
+            embed = embed.title(title);
+        }
+        if let Some(description) = &self.description {
+            embed = embed.description(description);
+        }
+        if let Some(url) = &self.url {
+            embed = embed.url(url);
+        }
+        if let Some(image) = &self.image {
+            embed = embed.image(image);
+        }
+        if let Some(colour) = self.colour {
+            embed = embed.colour(colour);
+        }
+        embed
+    }
+}
+
+const fn default_weight() -> u32 {
+    1
+}
+
+impl CommandMeta for ChoiceCommand {
+    const ID: &'static str = "ChoiceCommand";
+    const DESCRIPTION: &'static str = "Replies with a random or rotating response";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "mode": { "enum": ["random", "rotate"] },
+                "responses": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "body": { "type": "string", "minLength": 1 },
+                            "embed": {
+                                "type": "object",
+                                "properties": {
+                                    "title": { "type": "string" },
+                                    "description": { "type": "string" },
+                                    "url": { "type": "string" },
+                                    "image": { "type": "string" },
+                                    "colour": { "type": "integer", "minimum": 0, "maximum": 16777215 }
+                                }
+                            },
+                            "files": { "type": "array", "items": { "type": "string" } },
+                            "weight": { "type": "integer", "minimum": 0 }
+                        },
+                        "anyOf": [
+                            { "required": ["body"] },
+                            { "required": ["embed"] },
+                            { "required": ["files"] }
+                        ]
+                    }
+                },
+                "position": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["responses"]
+        })
+    }
+}
+
+impl ChoiceCommand {
+    /// Picks the index of the response to send, moving the rotation on
+    fn pick(&self) -> FlopResult<usize> {
+        let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(match data.mode {
+            ChoiceMode::Random => WeightedIndex::new(data.responses.iter().map(|x| x.weight))?
+                .sample(&mut rand::thread_rng()),
+            ChoiceMode::Rotate => {
+                let position = data.position % self.total;
+                data.position = (position + 1) % self.total;
+                // Find the response the position lands in
+                let mut acc = 0;
+                data.responses
+                    .iter()
+                    .position(|x| {
+                        acc += x.weight;
+                        acc > position
+                    })
+                    .unwrap_or_default()
+            }
+        })
+    }
+}
+
+/// Gets the stored files the responses in some saved data attach
+pub(super) fn stored_files(data: &[u8]) -> Vec<String> {
+    rmp_serde::from_slice::<ChoiceData>(data).map_or_else(
+        |_| Vec::new(),
+        |data| data.responses.into_iter().flat_map(|x| x.files).collect(),
+    )
+}
+
+#[async_trait]
+impl Command for ChoiceCommand {
+    fn construct(cli: &Cli, data: &[u8]) -> FlopResult<Self> {
+        let data: ChoiceData = rmp_serde::from_slice(data)?;
+        let dir = cli.get_path(ATTACHMENT_DIR);
+        for response in &data.responses {
+            if response.body.is_none() && response.embed.is_none() && response.files.is_empty() {
+                color_eyre::eyre::bail!("Every response needs a body, an embed or files");
+            }
+            for path in &response.files {
+                checked_path(&dir, path)?;
+            }
+        }
+        let total = data
+            .responses
+            .iter()
+            .try_fold(0u32, |acc, x| acc.checked_add(x.weight))
+            .ok_or_else(|| color_eyre::eyre::eyre!("Total weight is too large"))?;
+        if total == 0 {
+            color_eyre::eyre::bail!("There must be at least one response with a weight above 0");
+        }
+
+        Ok(Self {
+            data: Mutex::new(data),
+            total,
+            dir,
+        })
+    }
+
+    async fn execute<'a>(&self, _msg: &Message, _ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let index = self.pick()?;
+        // Don't hold the lock while reading files
+        let (mut out, paths) = {
+            let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+            let response = &data.responses[index];
+            let mut out = Vec::with_capacity(3);
+            if let Some(body) = &response.body {
+                out.push(FlopMessagable::Text(body.clone()));
+            }
+            if let Some(embed) = &response.embed {
+                out.push(FlopMessagable::Embeds(vec![embed.build()]));
+            }
+            (out, response.files.clone())
+        };
+        if !paths.is_empty() {
+            let files = read_files(&self.dir, &paths).await;
+            if !files.is_empty() {
+                out.push(FlopMessagable::Attachments(files));
+            }
+        }
+        if out.is_empty() {
+            out.push(FlopMessagable::Text(
+                "⚠️ This response's files are missing".to_owned(),
+            ));
+        }
+
+        Ok(FlopMessagable::Combined(out))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        match rmp_serde::to_vec_named(&*data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Error serialising choice command: `{e}`");
+                None
+            }
+        }
+    }
+}
@@ -1,19 +1,29 @@
+use std::sync::Arc;
+
 use serenity::{all::Mentionable, async_trait, model::channel::Message};
 use tokio::sync::RwLock;
 
 use crate::{
-    command::{inner::CmdCtx, ExtendedCommand, FlopMessagable},
-    sql::{CmdNode, FlopDB},
+    command::{
+        construct, inner::CmdCtx, is_valid, json_to_data, validate_data, CommandMeta,
+        ExtendedCommand, FlopMessagable,
+    },
+    sql::{CmdNode, FlopDB, FlopRole},
     Cli, FlopResult,
 };
 
-use super::MessageCommand;
+use super::{AttachmentCommand, MessageCommand};
 
 #[derive(Debug)]
 pub struct EditCommand {
     cli: Cli,
 }
 
+impl CommandMeta for EditCommand {
+    const ID: &'static str = "EditCommand";
+    const DESCRIPTION: &'static str = "Edits a command you own";
+}
+
 #[async_trait]
 impl ExtendedCommand for EditCommand {
     fn construct(cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
@@ -21,59 +31,122 @@ impl ExtendedCommand for EditCommand {
     }
 
     async fn execute<'b>(
-        &mut self,
+        &self,
         msg: &Message,
         ctx: CmdCtx<'b>,
         db: &RwLock<FlopDB>,
     ) -> FlopResult<FlopMessagable> {
         let args = msg.content.trim_start_matches(ctx.command).trim();
-        let Some((name, body)) = args.split_once(char::is_whitespace) else {
-            return Ok(FlopMessagable::Text(format!(
-                "Usage: `{} (name) (body)`",
+        let (name, body) = match args.split_once(char::is_whitespace) {
+            Some(split) => split,
+            // Commands with files dont need a body
+            None if !args.is_empty() && !msg.attachments.is_empty() => (args, ""),
+            None => {
+                return Ok(FlopMessagable::Text(format!(
+                "Usage: `{0} (name) (body)`\nor `{0} (name) (json data)` for other command types",
                 ctx.command
-            )));
+            )))
+            }
         };
 
-        // Special case this command to not cause a mutex gridlock
-        if ctx.name == name.to_lowercase() {
-            return Ok(FlopMessagable::Text(
-                "Insufficent perms to edit this command".to_owned(),
-            ));
-        }
-
         // get db lock
-        let mut db_lock = db.write().await;
+        let db_lock = db.read().await;
         let Some(cmd) = db_lock.get_command(ctx.registry.to_string(), name.to_string()) else {
-            return Ok(FlopMessagable::Text(format!("⚠️ `{name}` is not a command")));
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{name}` is not a command"
+            )));
         };
+        drop(db_lock);
 
         // Check if the command is owned by the person executing the command
-        let mut cmd_lock = cmd.lock().await;
+        let cmd_lock = cmd.lock().await;
         if cmd_lock.get_owner() != &msg.author.id {
             return Ok(FlopMessagable::Text(format!(
                 "⚠️ `{name}` is owned by {}",
-                cmd.lock().await.get_owner().mention()
+                cmd_lock.get_owner().mention()
             )));
         }
 
-        // Check if old command was a text command
-        if cmd_lock.get_type() != stringify!(MessageCommand) {
-            return Ok(FlopMessagable::Text(format!(
-                "⚠️ `{name}` is not a text command"
-            )));
-        }
+        // Text commands are edited with a new body, other types with new json data
+        let old_ty = cmd_lock.get_type().to_owned();
+        let is_text = old_ty == MessageCommand::ID || old_ty == AttachmentCommand::ID;
+        // Dont hold the command while downloading any files
+        drop(cmd_lock);
 
         // Construct new command
-        let new_cmd = match MessageCommand::construct(&self.cli, body.as_bytes()) {
-            Ok(new_cmd) => new_cmd,
-            Err(e) => {
+        let (ty, new_cmd): (_, Box<dyn ExtendedCommand + Send + Sync>) = if !is_text {
+            if !is_valid(&old_ty) {
                 return Ok(FlopMessagable::Text(format!(
-                    "⚠️ Error constructing command: `{e:?}`"
+                    "⚠️ `{name}` cannot be edited"
                 )));
             }
+            if !db
+                .read()
+                .await
+                .user_has_role(msg.author.id, &FlopRole::RegMod(ctx.registry.to_owned()))
+            {
+                return Ok(FlopMessagable::Text(":clueless:".to_string()));
+            }
+
+            if let Err(e) = validate_data(&old_ty, body) {
+                return Ok(FlopMessagable::Text(e));
+            }
+            let data = match json_to_data(body) {
+                Ok(data) => data,
+                Err(e) => return Ok(FlopMessagable::Text(e)),
+            };
+            match construct(&old_ty, &data, &self.cli) {
+                Ok(new_cmd) => (old_ty.as_str(), new_cmd),
+                Err(e) => {
+                    return Ok(FlopMessagable::Text(format!(
+                        "⚠️ Error constructing command: `{e:?}`"
+                    )));
+                }
+            }
+        } else if msg.attachments.is_empty() {
+            match MessageCommand::construct(&self.cli, body.as_bytes()) {
+                Ok(new_cmd) => (MessageCommand::ID, Box::new(new_cmd)),
+                Err(e) => {
+                    return Ok(FlopMessagable::Text(format!(
+                        "⚠️ Error constructing command: `{e:?}`"
+                    )));
+                }
+            }
+        } else {
+            match AttachmentCommand::from_message(&self.cli, &ctx.cfg.attachments, msg, body)
+                .await?
+            {
+                Ok(new_cmd) => (AttachmentCommand::ID, Box::new(new_cmd)),
+                Err(e) => return Ok(FlopMessagable::Text(e)),
+            }
         };
 
-        *cmd_lock.get_node() = CmdNode::Cmd(Box::new(new_cmd));
+        let mut db_lock = db.write().await;
+        let mut cmd_lock = cmd.lock().await;
+        // The command was unlocked while the new one was made, so check nothing changed
+        let still_there = db_lock
+            .get_command(ctx.registry.to_string(), name.to_string())
+            .is_some_and(|x| Arc::ptr_eq(&x, &cmd));
+        if !still_there || cmd_lock.get_owner() != &msg.author.id || cmd_lock.get_type() != old_ty {
+            drop(cmd_lock);
+            drop(db_lock);
+            if ty == AttachmentCommand::ID {
+                if let Some(data) = new_cmd.save() {
+                    AttachmentCommand::remove_files(&self.cli, &data, db).await;
+                }
+            }
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{name}` was changed while it was being edited, try again"
+            )));
+        }
+
+        // Files from before the edit aren't used anymore
+        let old_files = (old_ty == AttachmentCommand::ID)
+            .then(|| cmd_lock.get_node().save())
+            .flatten();
+        *cmd_lock.get_node() = CmdNode::Cmd(new_cmd.into());
+        cmd_lock.set_type(ty.to_owned());
+        cmd_lock.set_edited(msg.author.id);
         db_lock.mark_dirty(
             cmd_lock.get_registry().to_owned(),
             cmd_lock.get_name().to_owned(),
@@ -83,6 +156,10 @@ impl ExtendedCommand for EditCommand {
         drop(cmd_lock);
         drop(db_lock);
 
+        if let Some(data) = old_files {
+            AttachmentCommand::remove_files(&self.cli, &data, db).await;
+        }
+
         Ok(FlopMessagable::Text(format!("Edited command `{name}`")))
     }
 
@@ -1,34 +1,193 @@
 use std::sync::atomic::Ordering;
 
-use serenity::{async_trait, model::prelude::Message};
+use serenity::{
+    all::{CreateEmbed, Mentionable},
+    async_trait,
+    model::prelude::*,
+};
+use time::{OffsetDateTime, Time};
+use tokio::sync::RwLock;
 
 use crate::{
-    command::{inner::CmdCtx, Command, FlopMessagable},
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
     handler::REACTION_COUNT,
+    sql::{FlopDB, FlopRole},
+    stuff::next_word,
     Cli, FlopResult,
 };
 
+/// How many places are shown on leaderboards
+const LEADERBOARD_SIZE: i64 = 10;
+
 #[derive(Debug)]
 pub struct FlopCountCommand;
 
+impl CommandMeta for FlopCountCommand {
+    const ID: &'static str = "FlopCountCommand";
+    const DESCRIPTION: &'static str =
+        "Shows how many flops have been reacted to, with leaderboards for users and channels";
+}
+
+/// How far back counts go, all of them are since the last reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Window {
+    Today,
+    Week,
+    All,
+}
+
+impl Window {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "today" | "day" => Some(Self::Today),
+            "week" => Some(Self::Week),
+            "" | "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    /// The unix timestamp the window starts at, today and this week start at midnight UTC
+    fn since(self) -> i64 {
+        let today = OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT);
+        match self {
+            Self::Today => today.unix_timestamp(),
+            Self::Week => {
+                let days = today.weekday().number_days_from_monday();
+                (today - time::Duration::days(days.into())).unix_timestamp()
+            }
+            Self::All => 0,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Today => "today",
+            Self::Week => "this week",
+            Self::All => "since the last reset",
+        }
+    }
+}
+
 #[async_trait]
-impl Command for FlopCountCommand {
+impl ExtendedCommand for FlopCountCommand {
     fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
         Ok(Self)
     }
 
     async fn execute<'a>(
-        &mut self,
-        _msg: &Message,
-        _ctx: CmdCtx<'a>,
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'a>,
+        db: &RwLock<FlopDB>,
     ) -> FlopResult<FlopMessagable> {
-        Ok(FlopMessagable::Text(format!(
-            "{} flops reacted to since last reset",
-            REACTION_COUNT.load(Ordering::Relaxed)
-        )))
+        let args = msg.content.trim_start_matches(ctx.command);
+        let (sub, rest) = next_word(args);
+
+        let (board, window) = match sub {
+            "reset" => return reset(msg, db).await,
+            "top" | "users" => (Some(Board::Users), next_word(rest).0),
+            "channels" => (Some(Board::Channels), next_word(rest).0),
+            x => (None, x),
+        };
+        let Some(window) = Window::parse(window) else {
+            return Ok(FlopMessagable::Text(format!(
+                "Usage: `{0} [top|channels] [today|week|all]`, or `{0} reset` to archive the counts",
+                ctx.command
+            )));
+        };
+
+        let db = db.read().await;
+        let since = window.since();
+        match board {
+            Some(board) => leaderboard(msg, &db, board, window).await,
+            None => {
+                // The global total also has the reactions from before they were tracked
+                let total = match window {
+                    Window::All => REACTION_COUNT.load(Ordering::Relaxed).into(),
+                    _ => db.count_reactions(None, since).await?,
+                };
+                let mut text = format!("{total} flops reacted to {}", window.name());
+                if msg.guild_id.is_some() {
+                    let guild = db.count_reactions(msg.guild_id, since).await?;
+                    text.push_str(&format!(", {guild} in this server"));
+                }
+                Ok(FlopMessagable::Text(text))
+            }
+        }
     }
 
     fn save(&self) -> Option<Vec<u8>> {
         None
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+enum Board {
+    Users,
+    Channels,
+}
+
+/// Shows who or where gets reacted to the most, in this server or everywhere in DMs
+async fn leaderboard(
+    msg: &Message,
+    db: &FlopDB,
+    board: Board,
+    window: Window,
+) -> FlopResult<FlopMessagable> {
+    let since = window.since();
+    let (title, places) = match board {
+        Board::Users => (
+            "Most flopped users",
+            db.reaction_users(msg.guild_id, since, LEADERBOARD_SIZE)
+                .await?
+                .into_iter()
+                .map(|(user, count)| (user.mention(), count))
+                .collect::<Vec<_>>(),
+        ),
+        Board::Channels => (
+            "Most flopped channels",
+            db.reaction_channels(msg.guild_id, since, LEADERBOARD_SIZE)
+                .await?
+                .into_iter()
+                .map(|(channel, count)| (channel.mention(), count))
+                .collect(),
+        ),
+    };
+
+    if places.is_empty() {
+        return Ok(FlopMessagable::Text(format!(
+            "Nothing has been flopped {}",
+            window.name()
+        )));
+    }
+
+    let text = places
+        .iter()
+        .enumerate()
+        .map(|(i, (who, count))| format!("{}. {who}: {count}", i + 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let scope = if msg.guild_id.is_some() {
+        "in this server"
+    } else {
+        "everywhere"
+    };
+    Ok(CreateEmbed::new()
+        .title(title)
+        .description(format!("{}, {scope}\n\n{text}", window.name()))
+        .into())
+}
+
+/// Archives the counts, so they start again from 0
+async fn reset(msg: &Message, db: &RwLock<FlopDB>) -> FlopResult<FlopMessagable> {
+    let db = db.read().await;
+    if !db.user_has_role(msg.author.id, &FlopRole::Admin) {
+        return Ok(FlopMessagable::Text(":clueless:".to_string()));
+    }
+
+    let count = db.reset_reactions(msg.author.id).await?;
+    REACTION_COUNT.store(0, Ordering::Relaxed);
+    Ok(FlopMessagable::Text(format!(
+        "Archived {count} flops, the count starts again from 0"
+    )))
+}
@@ -0,0 +1,86 @@
+use serenity::{async_trait, model::channel::Message};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    github::valid_repo,
+    sql::{FlopDB, FlopRole},
+    stuff::next_word,
+    Cli, FlopResult,
+};
+
+#[derive(Debug)]
+pub struct GithubCommand;
+
+impl CommandMeta for GithubCommand {
+    const ID: &'static str = "GithubCommand";
+    const DESCRIPTION: &'static str =
+        "Sets the GitHub repo that issue references like `#1234` are expanded from in this server";
+}
+
+#[async_trait]
+impl ExtendedCommand for GithubCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let Some(guild) = msg.guild_id else {
+            return Ok(FlopMessagable::Text("⚠️ This isn't a server".to_string()));
+        };
+        let args = msg.content.trim_start_matches(ctx.command);
+        let (sub, rest) = next_word(args);
+
+        if sub.is_empty() {
+            return Ok(FlopMessagable::Text(
+                match db.read().await.github_repo(guild) {
+                    Some(repo) => format!("Issue references here are expanded from `{repo}`"),
+                    None => format!(
+                        "Issue references aren't expanded here, set a repo with `{} set <owner/name>`",
+                        ctx.command
+                    ),
+                },
+            ));
+        }
+
+        let mut db_lock = db.write().await;
+        if !db_lock.user_has_role(msg.author.id, &FlopRole::GlobalMod) {
+            return Ok(FlopMessagable::Text(":clueless:".to_string()));
+        }
+
+        let text = match sub {
+            "set" => {
+                let (repo, _) = next_word(rest);
+                if valid_repo(repo) {
+                    db_lock
+                        .set_github_repo(guild, Some(repo.to_owned()))
+                        .await?;
+                    format!("Issue references here are now expanded from `{repo}`")
+                } else {
+                    format!("⚠️ `{repo}` is not a repo, write it like `owner/name`")
+                }
+            }
+            "off" | "unset" => {
+                db_lock.set_github_repo(guild, None).await?;
+                "Issue references here are no longer expanded".to_string()
+            }
+            _ => format!(
+                "Usage:```
+{0}
+{0} set <owner/name>
+{0} off```",
+                ctx.command
+            ),
+        };
+        Ok(FlopMessagable::Text(text))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
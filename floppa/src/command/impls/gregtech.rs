@@ -0,0 +1,287 @@
+use std::{
+    path::{Component, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter},
+    async_trait,
+    model::channel::Message,
+};
+use tokio::sync::RwLock;
+use tracing::error;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    gregtech::{tier_for, GtData, Material, Recipe, Stack, TIERS},
+    sql::{FlopDB, FlopRole},
+    stuff::next_word,
+    Cli, FlopResult,
+};
+
+/// The data file used if the command doesn't give one, in the run dir
+const DEFAULT_FILE: &str = "gtceu.json";
+/// How many other matches are suggested
+const SUGGESTIONS: usize = 5;
+/// The most recipes shown for one search
+const MAX_RECIPES: usize = 25;
+
+#[derive(Debug)]
+pub struct GregTechCommand {
+    path: PathBuf,
+    config: GregTechData,
+    /// Loaded when first used, and again when reloaded
+    data: RwLock<Option<Arc<GtData>>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GregTechData {
+    /// The path of the data file, relative to the run dir
+    #[serde(default)]
+    file: Option<String>,
+}
+
+impl CommandMeta for GregTechCommand {
+    const ID: &'static str = "GregTechCommand";
+    const DESCRIPTION: &'static str =
+        "Looks up GregTech materials and recipes from a data file in the run dir";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": ["object", "null"],
+            "properties": {
+                "file": { "type": "string", "minLength": 1 }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl ExtendedCommand for GregTechCommand {
+    fn construct(cli: &Cli, data: &[u8]) -> FlopResult<Self> {
+        let config: GregTechData = if data.is_empty() {
+            GregTechData::default()
+        } else {
+            rmp_serde::from_slice(data)?
+        };
+        let file = PathBuf::from(config.file.as_deref().unwrap_or(DEFAULT_FILE));
+        if !file.components().all(|x| matches!(x, Component::Normal(_))) {
+            color_eyre::eyre::bail!("The data file must be inside the run dir");
+        }
+
+        Ok(Self {
+            path: cli.get_path(file),
+            config,
+            data: RwLock::new(None),
+        })
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command);
+        let (sub, rest) = next_word(args);
+        let query = rest.trim();
+
+        if sub == "reload" {
+            if !db
+                .read()
+                .await
+                .user_has_role(msg.author.id, &FlopRole::GlobalMod)
+            {
+                return Ok(FlopMessagable::Text(":clueless:".to_string()));
+            }
+            return Ok(FlopMessagable::Text(match self.reload().await {
+                Ok(data) => format!(
+                    "Loaded {} materials and {} recipes",
+                    data.materials.len(),
+                    data.recipes.len()
+                ),
+                Err(e) => e,
+            }));
+        }
+
+        let data = match self.get_data().await {
+            Ok(data) => data,
+            Err(e) => return Ok(FlopMessagable::Text(e)),
+        };
+        Ok(match sub {
+            "material" | "mat" | "m" if !query.is_empty() => material(&data, query),
+            "recipe" | "r" if !query.is_empty() => recipes(&data, query, true),
+            "uses" | "u" if !query.is_empty() => recipes(&data, query, false),
+            _ => FlopMessagable::Text(usage(ctx.command)),
+        })
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        match rmp_serde::to_vec_named(&self.config) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Error serialising gregtech command: `{e}`");
+                None
+            }
+        }
+    }
+}
+
+impl GregTechCommand {
+    /// Gets the loaded data, loading it if it hasn't been yet
+    async fn get_data(&self) -> Result<Arc<GtData>, String> {
+        if let Some(data) = &*self.data.read().await {
+            return Ok(data.clone());
+        }
+        self.reload().await
+    }
+
+    /// Loads the data file again, errors are a message to show the user
+    async fn reload(&self) -> Result<Arc<GtData>, String> {
+        let mut lock = self.data.write().await;
+        match GtData::load(&self.path).await {
+            Ok(data) => {
+                let data = Arc::new(data);
+                *lock = Some(data.clone());
+                Ok(data)
+            }
+            Err(e) => {
+                error!("Error loading `{}`: {e}", self.path.display());
+                Err(format!("⚠️ Could not load the GregTech data: `{e}`"))
+            }
+        }
+    }
+}
+
+fn usage(command: &str) -> String {
+    format!(
+        "Usage:```
+{command} material <name>
+{command} recipe <output>
+{command} uses <input>
+{command} reload```"
+    )
+}
+
+/// Lists the other matches, for the footer
+fn others<'a>(names: impl Iterator<Item = &'a str>) -> Option<String> {
+    let names = names.take(SUGGESTIONS).collect::<Vec<_>>();
+    (!names.is_empty()).then(|| format!("Also matched: {}", names.join(", ")))
+}
+
+fn material(data: &GtData, query: &str) -> FlopMessagable {
+    let found = data.find_materials(query);
+    let Some(material) = found.first() else {
+        return FlopMessagable::Text(format!("⚠️ There is no material like `{query}`"));
+    };
+
+    let mut embed = material_embed(material);
+    if let Some(footer) = others(found[1..].iter().map(|x| x.name.as_str())) {
+        embed = embed.footer(CreateEmbedFooter::new(footer));
+    }
+    embed.into()
+}
+
+fn material_embed(material: &Material) -> CreateEmbed {
+    let mut embed = CreateEmbed::new().title(&material.name);
+    if let Some(formula) = &material.formula {
+        embed = embed.description(format!("`{formula}`"));
+    }
+    if let Some(color) = material.color {
+        embed = embed.colour(color);
+    }
+    if !material.components.is_empty() {
+        embed = embed.field("Composition", stack_list(&material.components, ""), false);
+    }
+    if let Some(tier) = &material.tier {
+        embed = embed.field("Tier", tier, true);
+    }
+    if let Some(temperature) = material.blast_temperature {
+        embed = embed.field("Blast temperature", format!("{temperature}K"), true);
+    }
+    if !material.flags.is_empty() {
+        embed = embed.field("Forms", material.flags.join(", "), false);
+    }
+    embed
+}
+
+/// Shows the recipes that make or use something, a page each
+fn recipes(data: &GtData, query: &str, outputs: bool) -> FlopMessagable {
+    let Some(name) = data.find_stack_name(query, outputs) else {
+        let what = if outputs { "makes" } else { "uses" };
+        return FlopMessagable::Text(format!("⚠️ No recipe {what} anything like `{query}`"));
+    };
+    let found = if outputs {
+        data.recipes_making(name)
+    } else {
+        data.recipes_using(name)
+    };
+
+    let count = found.len().min(MAX_RECIPES);
+    let pages = found
+        .iter()
+        .take(MAX_RECIPES)
+        .enumerate()
+        .map(|(i, recipe)| {
+            let more = match found.len() {
+                x if x > MAX_RECIPES => format!(", {} more not shown", x - MAX_RECIPES),
+                _ => String::new(),
+            };
+            recipe_embed(recipe, name)
+                .footer(CreateEmbedFooter::new(format!(
+                    "Recipe {}/{count}{more}",
+                    i + 1
+                )))
+                .into()
+        })
+        .collect();
+    FlopMessagable::Pages(pages)
+}
+
+fn recipe_embed(recipe: &Recipe, name: &str) -> CreateEmbed {
+    let mut inputs = stack_list(&recipe.inputs, "");
+    inputs.push_str(&stack_list(&recipe.fluid_inputs, "mB"));
+    let mut outputs = stack_list(&recipe.outputs, "");
+    outputs.push_str(&stack_list(&recipe.fluid_outputs, "mB"));
+
+    let tier = TIERS[tier_for(recipe.eut)].0;
+    let seconds = recipe.duration as f64 / 20.0;
+    CreateEmbed::new()
+        .title(format!("{name} ({})", recipe.kind.replace('_', " ")))
+        .field("Inputs", or_none(inputs), true)
+        .field("Outputs", or_none(outputs), true)
+        .field("Power", format!("{} EU/t ({tier})", recipe.eut), false)
+        .field(
+            "Duration",
+            format!("{seconds}s ({} ticks)", recipe.duration),
+            true,
+        )
+        .field(
+            "Total",
+            format!("{} EU", recipe.eut.saturating_mul(recipe.duration)),
+            true,
+        )
+}
+
+/// Writes out stacks a line each, with the unit after the amount
+fn stack_list(stacks: &[Stack], unit: &str) -> String {
+    stacks
+        .iter()
+        .map(|x| {
+            let chance = match x.chance {
+                Some(chance) if chance < 10000 => format!(" ({}%)", chance as f64 / 100.0),
+                _ => String::new(),
+            };
+            format!("{}{unit} {}{chance}\n", x.amount, x.name)
+        })
+        .collect()
+}
+
+fn or_none(text: String) -> String {
+    if text.is_empty() {
+        "None".to_string()
+    } else {
+        text
+    }
+}
@@ -1,15 +1,30 @@
-use serenity::{all::Mentionable, async_trait, model::channel::Message};
+use serenity::{
+    all::{CreateEmbed, Mentionable},
+    async_trait,
+    model::channel::Message,
+};
 use tokio::sync::RwLock;
 
 use crate::{
-    command::{inner::CmdCtx, ExtendedCommand, FlopMessagable},
-    sql::FlopDB,
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    sql::{CmdNode, FlopDB},
+    stuff::truncate,
     Cli, FlopResult,
 };
 
+use super::MessageCommand;
+
+/// The max length of an embed field's value
+const FIELD_LIMIT: usize = 1024;
+
 #[derive(Debug)]
 pub struct InfoCommand;
 
+impl CommandMeta for InfoCommand {
+    const ID: &'static str = "InfoCommand";
+    const DESCRIPTION: &'static str = "Shows information about a command";
+}
+
 #[async_trait]
 impl ExtendedCommand for InfoCommand {
     fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
@@ -17,7 +32,7 @@ impl ExtendedCommand for InfoCommand {
     }
 
     async fn execute<'b>(
-        &mut self,
+        &self,
         msg: &Message,
         ctx: CmdCtx<'b>,
         db: &RwLock<FlopDB>,
@@ -41,11 +56,21 @@ impl ExtendedCommand for InfoCommand {
         }
 
         let db_lock = db.read().await;
-        let Some(cmd) = db_lock.get_command(registry.to_owned(), name.to_owned()) else {
+        let canonicalised = db_lock
+            .canonicalise_command(registry.to_owned(), name.to_owned())
+            .await;
+
+        // Find the first command in the chain that exists, as the name may be inherited
+        let found = canonicalised.stack.iter().find_map(|(reg, name)| {
+            db_lock
+                .get_command(reg.to_owned(), name.to_owned())
+                .map(|cmd| (reg, name, cmd))
+        });
+        let Some((found_reg, found_name, cmd)) = found else {
             let end = if registry != ctx.registry {
                 format!(" in registry `{registry}`")
             } else {
-                "".to_owned()
+                String::new()
             };
 
             return Ok(FlopMessagable::Text(format!(
@@ -56,37 +81,82 @@ impl ExtendedCommand for InfoCommand {
         // Drop lock to free db for other uses
         drop(db_lock);
 
-        // Special case this command to not cause a mutex gridlock
-        if ctx.registry == registry && ctx.name == name.to_lowercase() {
-            return Ok(FlopMessagable::Text(format!(
-                "Command `{}` was added at <t:{}:f>, and is owned by {}",
-                ctx.name,
-                ctx.added,
-                ctx.owner.mention()
-            )));
-        }
-
-        let cmd_lock = cmd.lock().await;
-
-        let owner = cmd_lock.get_owner();
-        let registry = cmd_lock.get_registry();
-        let name = cmd_lock.get_name();
-        let added = cmd_lock.get_added();
+        // The chain of commands from what was asked for, to what actually gets ran
+        let chain = canonicalised
+            .stack
+            .iter()
+            .skip_while(|(reg, name)| reg != found_reg || name != found_name)
+            .map(|(reg, name)| format!("`{reg}:{name}`"))
+            .collect::<Vec<_>>();
+
+        let mut cmd_lock = cmd.lock().await;
+
+        let mut embed = CreateEmbed::new()
+            .title(format!(
+                "{}:{}",
+                cmd_lock.get_registry(),
+                cmd_lock.get_name()
+            ))
+            .field("Type", format!("`{}`", cmd_lock.get_type()), true)
+            .field("Owner", cmd_lock.get_owner().mention().to_string(), true)
+            .field("Added", format!("<t:{}:f>", cmd_lock.get_added()), true)
+            .field("Uses", cmd_lock.get_uses().to_string(), true)
+            .field(
+                "Last used",
+                match cmd_lock.get_last_used() {
+                    Some(time) => format!("<t:{time}:R>"),
+                    None => "Never".to_string(),
+                },
+                true,
+            );
+
+        embed = match cmd_lock.get_edited() {
+            Some((editor, edited)) => embed.field(
+                "Last edited",
+                format!("<t:{edited}:f> by {}", editor.mention()),
+                true,
+            ),
+            None => embed.field("Last edited", "Never", true),
+        };
 
-        let mut msg = format!("Command `{name}`");
-        if registry != ctx.registry {
-            msg += &format!(" in registry `{registry}`")
+        if chain.len() > 1 {
+            embed = embed.field(
+                "Resolves through",
+                truncate(&chain.join(" -> "), FIELD_LIMIT),
+                false,
+            );
         }
 
-        msg += &format!(
-            " was added at <t:{added}:f>, and is owned by {}",
-            owner.mention()
-        );
+        let is_text = cmd_lock.get_type() == MessageCommand::ID;
+        embed = match cmd_lock.get_node() {
+            CmdNode::Symlink { reg, name } => {
+                embed.field("Symlink to", format!("`{reg}:{name}`"), false)
+            }
+            CmdNode::Subregistry(reg) => embed.field("Subregistry of", format!("`{reg}`"), false),
+            CmdNode::Cmd(cmd) => match cmd.raw() {
+                Some(raw) if is_text => embed.field("Body", code_block(&raw, ""), false),
+                Some(raw) => embed.field("Data", code_block(&raw, "json"), false),
+                None => embed,
+            },
+            CmdNode::Broken { error, .. } => {
+                embed.field("Failed to load", code_block(error, ""), false)
+            }
+        };
 
-        Ok(FlopMessagable::Text(msg))
+        Ok(embed.into())
     }
 
     fn save(&self) -> Option<Vec<u8>> {
         None
     }
 }
+
+/// Wraps text in a code block, truncating it to fit in an embed field
+fn code_block(text: &str, lang: &str) -> String {
+    // Room for the backticks, newlines and the language
+    let limit = FIELD_LIMIT - 8 - lang.len();
+    format!(
+        "```{lang}\n{}\n```",
+        truncate(&text.replace("```", "`\u{200b}``"), limit)
+    )
+}
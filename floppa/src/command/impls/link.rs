@@ -2,7 +2,7 @@ use serenity::{all::Mentionable, async_trait, model::channel::Message};
 use tokio::sync::RwLock;
 
 use crate::{
-    command::{check_name, inner::CmdCtx, ExtendedCommand, FlopMessagable},
+    command::{check_name, inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
     sql::{CmdNode, FlopDB, FlopRole},
     Cli, FlopResult,
 };
@@ -10,6 +10,11 @@ use crate::{
 #[derive(Debug)]
 pub struct LinkCommand;
 
+impl CommandMeta for LinkCommand {
+    const ID: &'static str = "LinkCommand";
+    const DESCRIPTION: &'static str = "Links a name to another command or registry";
+}
+
 #[async_trait]
 impl ExtendedCommand for LinkCommand {
     fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
@@ -18,7 +23,7 @@ impl ExtendedCommand for LinkCommand {
 
     // TODO: roles
     async fn execute<'b>(
-        &mut self,
+        &self,
         msg: &Message,
         ctx: CmdCtx<'b>,
         db: &RwLock<FlopDB>,
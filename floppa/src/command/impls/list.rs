@@ -0,0 +1,87 @@
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter},
+    async_trait,
+    model::channel::Message,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    sql::FlopDB,
+    Cli, FlopResult,
+};
+
+/// How many command names are shown on each page
+const PER_PAGE: usize = 25;
+
+#[derive(Debug)]
+pub struct ListCommand;
+
+impl CommandMeta for ListCommand {
+    const ID: &'static str = "ListCommand";
+    const DESCRIPTION: &'static str = "Lists the commands in a registry";
+}
+
+#[async_trait]
+impl ExtendedCommand for ListCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let registry = msg
+            .content
+            .trim_start_matches(ctx.command)
+            .split_whitespace()
+            .next()
+            .unwrap_or(ctx.registry);
+
+        let db_lock = db.read().await;
+        if !db_lock.registry_exists(registry) {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{registry}` is not a registry"
+            )));
+        }
+        let names = db_lock.list_commands(registry);
+        drop(db_lock);
+
+        if names.is_empty() {
+            return Ok(FlopMessagable::Text(format!(
+                "`{registry}` has no commands"
+            )));
+        }
+
+        let count = names.len().div_ceil(PER_PAGE);
+        let pages = names
+            .chunks(PER_PAGE)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let list = chunk
+                    .iter()
+                    .map(|x| format!("`{x}`"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                CreateEmbed::new()
+                    .title(format!("Commands in `{registry}`"))
+                    .description(list)
+                    .footer(CreateEmbedFooter::new(format!(
+                        "Page {}/{count}, {} commands",
+                        i + 1,
+                        names.len()
+                    )))
+                    .into()
+            })
+            .collect();
+
+        Ok(FlopMessagable::Pages(pages))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
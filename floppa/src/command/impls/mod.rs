@@ -1,23 +1,59 @@
 mod add;
+mod api;
+mod attachment;
+mod broken;
+mod calc;
+mod choice;
 mod edit;
 mod flop_count;
+mod github;
+mod gregtech;
 mod info;
 mod link;
+mod list;
+mod molar_mass;
 mod msg;
+mod overclock;
+mod plugins;
+mod raw;
+mod remind;
 mod remove;
+mod responders;
 mod role;
+mod schedule;
+mod script;
+mod shutdown;
 mod stoik;
+mod types;
 mod version;
 mod wiki;
 
 pub use add::AddCommand;
+pub use api::ApiCommand;
+pub use attachment::AttachmentCommand;
+pub use broken::BrokenCommand;
+pub use calc::CalcCommand;
+pub use choice::ChoiceCommand;
 pub use edit::EditCommand;
 pub use flop_count::FlopCountCommand;
+pub use github::GithubCommand;
+pub use gregtech::GregTechCommand;
 pub use info::InfoCommand;
 pub use link::LinkCommand;
+pub use list::ListCommand;
+pub use molar_mass::MolarMassCommand;
 pub use msg::MessageCommand;
+pub use overclock::OverclockCommand;
+pub use plugins::PluginsCommand;
+pub use raw::RawCommand;
+pub use remind::RemindCommand;
 pub use remove::RemoveCommand;
+pub use responders::RespondersCommand;
 pub use role::RoleCommand;
+pub use schedule::ScheduleCommand;
+pub use script::ScriptCommand;
+pub use shutdown::ShutdownCommand;
 pub use stoik::StoikCommand;
+pub use types::TypesCommand;
 pub use version::VersionCommand;
 pub use wiki::WikiCommand;
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use serenity::{
+    all::{CreateEmbed, Message},
+    async_trait,
+};
+use stoik::formula::Molecule;
+
+use crate::{
+    chem::{element, generate_stoik_error_msg},
+    command::{CmdCtx, Command, CommandMeta, FlopMessagable},
+    stuff::truncate,
+    Cli, FlopResult,
+};
+
+/// The max length of the formula in the title, leaving room for the mass in Discord's 256
+const FORMULA_LIMIT: usize = 200;
+
+const HELP_MSG: &str = "Usage: molar [FLAGS] ... [FORMULA]
+Computes the molar mass of FORMULA, and how much of it is each element
+  -h, --help Shows this";
+
+#[derive(Debug)]
+pub struct MolarMassCommand;
+
+impl CommandMeta for MolarMassCommand {
+    const ID: &'static str = "MolarMassCommand";
+    const DESCRIPTION: &'static str =
+        "Computes the molar mass of a chemical formula, and its composition by mass";
+}
+
+#[async_trait]
+impl Command for MolarMassCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command).trim();
+        if args.is_empty() || args.split_whitespace().any(|x| x == "--help" || x == "-h") {
+            return Ok(FlopMessagable::Text(HELP_MSG.to_string()));
+        }
+        // Spaces don't mean anything in a formula, so let people use them
+        let formula = args.split_whitespace().collect::<String>();
+
+        let counts = match Molecule::from_formula(&formula) {
+            Ok(molecule) => molecule.get_map(),
+            Err(e) => {
+                return Ok(FlopMessagable::Text(format!(
+                    "```{}```",
+                    generate_stoik_error_msg(e, &formula)
+                )))
+            }
+        };
+
+        let mut masses = BTreeMap::new();
+        for (symbol, count) in &counts {
+            let Some(element) = element(symbol) else {
+                return Ok(FlopMessagable::Text(format!(
+                    "⚠️ `{symbol}` is not an element"
+                )));
+            };
+            masses.insert(
+                symbol.as_str(),
+                (element, *count, element.mass * *count as f64),
+            );
+        }
+        let total = masses.values().map(|(_, _, mass)| mass).sum::<f64>();
+        if total <= 0.0 {
+            return Ok(FlopMessagable::Text(
+                "⚠️ That formula has no atoms in it".to_string(),
+            ));
+        }
+
+        let names = masses
+            .iter()
+            .map(|(symbol, (element, _, _))| format!("{symbol} ({})", element.name))
+            .fold(String::new(), |acc, x| format!("{acc}\n{x}"));
+        let atoms = masses
+            .values()
+            .map(|(_, count, _)| count)
+            .fold(String::new(), |acc, x| format!("{acc}\n{x}"));
+        let percentages = masses
+            .values()
+            .map(|(_, _, mass)| format!("{:.3}%", mass / total * 100.0))
+            .fold(String::new(), |acc, x| format!("{acc}\n{x}"));
+
+        let embed = CreateEmbed::new()
+            .title(format!(
+                "{}: {total:.3} g/mol",
+                truncate(&formula, FORMULA_LIMIT)
+            ))
+            .field("Element", format!("```{names}```"), true)
+            .field("Atoms", format!("```{atoms}```"), true)
+            .field("Mass", format!("```{percentages}```"), true);
+        Ok(embed.into())
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
@@ -2,7 +2,7 @@ use serenity::{async_trait, model::prelude::Message};
 use tracing::error;
 
 use crate::{
-    command::{inner::CmdCtx, Command, FlopMessagable},
+    command::{inner::CmdCtx, Command, CommandMeta, FlopMessagable},
     Cli, FlopResult,
 };
 
@@ -13,6 +13,12 @@ pub struct MessageCommand {
     message: FlopMessagable,
 }
 
+impl CommandMeta for MessageCommand {
+    const ID: &'static str = "MessageCommand";
+    const DESCRIPTION: &'static str =
+        "Replies with a fixed message, added with `add (name) (body)`";
+}
+
 #[async_trait]
 impl Command for MessageCommand {
     fn construct(_cli: &Cli, data: &[u8]) -> FlopResult<Self> {
@@ -21,11 +27,7 @@ impl Command for MessageCommand {
         })
     }
 
-    async fn execute<'a>(
-        &mut self,
-        _msg: &Message,
-        _ctx: CmdCtx<'a>,
-    ) -> FlopResult<FlopMessagable> {
+    async fn execute<'a>(&self, _msg: &Message, _ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
         Ok(self.message.clone())
     }
 
@@ -42,16 +44,16 @@ impl Command for MessageCommand {
         }
     }
 
-    // fn raw(&self) -> &str {
-    //     match &self.message {
-    //         FlopMessagable::Text(s) => s,
-    //         _ => {
-    //             error!(
-    //                 "Not supported value is trying to be seralised: `{:?}`",
-    //                 self.message
-    //             );
-    //             "⚠️**ERROR**⚠️ cannot get raw form of data for command"
-    //         }
-    //     }
-    // }
+    fn raw(&self) -> Option<String> {
+        match &self.message {
+            FlopMessagable::Text(s) => Some(s.clone()),
+            _ => {
+                error!(
+                    "Not supported value is trying to be seralised: `{:?}`",
+                    self.message
+                );
+                None
+            }
+        }
+    }
 }
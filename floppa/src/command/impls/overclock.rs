@@ -0,0 +1,206 @@
+use serenity::{
+    all::{CreateEmbed, Message},
+    async_trait,
+};
+
+use crate::{
+    command::{CmdCtx, Command, CommandMeta, FlopMessagable},
+    gregtech::{overclock, tier_by_name, tier_for, Overclock, TIERS},
+    Cli, FlopResult,
+};
+
+const HELP_MSG: &str = "Usage: overclock [FLAGS] ... EUT DURATION [TIER]
+Overclocks a recipe using EUT EU/t for DURATION, in ticks or with an s for seconds,
+on every tier up to TIER, or all of them
+Each overclock is 4x the EU/t, and half the time or a quarter for perfect overclocks
+  -a, --amps N     The amps the machine gets, for working out parallels, 1 by default
+  -p, --parallel N The most parallels the machine can do, none by default
+  -h, --help       Shows this";
+
+#[derive(Debug)]
+pub struct OverclockCommand;
+
+impl CommandMeta for OverclockCommand {
+    const ID: &'static str = "OverclockCommand";
+    const DESCRIPTION: &'static str =
+        "Works out GregTech overclocks and parallels for a recipe on each voltage tier";
+}
+
+#[async_trait]
+impl Command for OverclockCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command).trim();
+        let mut amps = 1;
+        let mut max_parallel = 1;
+        let mut positional = Vec::new();
+        let mut args = args.split_whitespace();
+        while let Some(arg) = args.next() {
+            match arg {
+                "-h" | "--help" => return Ok(FlopMessagable::Text(HELP_MSG.to_string())),
+                "-a" | "--amps" | "-p" | "--parallel" => {
+                    let Some(value) = args.next().and_then(|x| x.parse().ok()).filter(|x| *x > 0)
+                    else {
+                        return Ok(FlopMessagable::Text(format!(
+                            "⚠️ `{arg}` needs a number above 0 after it"
+                        )));
+                    };
+                    match arg {
+                        "-a" | "--amps" => amps = value,
+                        _ => max_parallel = value,
+                    }
+                }
+                x => positional.push(x),
+            }
+        }
+
+        let (eut, duration, tier) = match positional[..] {
+            [eut, duration] => (eut, duration, TIERS[TIERS.len() - 1].0),
+            [eut, duration, tier] => (eut, duration, tier),
+            _ => return Ok(FlopMessagable::Text(HELP_MSG.to_string())),
+        };
+        let Some(eut) = eut
+            .trim_end_matches("EU/t")
+            .trim_end_matches("eu/t")
+            .parse::<u64>()
+            .ok()
+            .filter(|x| *x > 0)
+        else {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{eut}` is not a valid EU/t"
+            )));
+        };
+        let Some(duration) = parse_ticks(duration) else {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{duration}` is not a valid duration, use ticks or seconds like `10s`"
+            )));
+        };
+        let Some(tier) = tier_by_name(tier) else {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{tier}` is not a voltage tier, expected one of {}",
+                TIERS.map(|x| x.0).join(", ")
+            )));
+        };
+
+        let base = tier_for(eut);
+        if tier < base {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ A {} machine can't run a recipe using {eut} EU/t, it needs at least {}",
+                TIERS[tier].0, TIERS[base].0
+            )));
+        }
+
+        let standard = table(eut, duration, base, tier, amps, max_parallel, false);
+        let perfect = table(eut, duration, base, tier, amps, max_parallel, true);
+        let amp_text = if amps == 1 {
+            String::new()
+        } else {
+            format!(" with {amps}A")
+        };
+        let description = if max_parallel == 1 {
+            format!("Overclocks up to {}{amp_text}", TIERS[tier].0)
+        } else {
+            format!(
+                "Runs up to {max_parallel} parallels on each tier up to {}{amp_text}, \
+                then overclocks with the power left over",
+                TIERS[tier].0
+            )
+        };
+        let embed = CreateEmbed::new()
+            .title(format!(
+                "{eut} EU/t ({}) for {}",
+                TIERS[base].0,
+                format_ticks(duration)
+            ))
+            .description(description)
+            .field("Standard overclocks", format!("```{standard}```"), false)
+            .field("Perfect overclocks", format!("```{perfect}```"), false);
+        Ok(embed.into())
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// Parses a duration in ticks, or in seconds if it ends with `s`
+fn parse_ticks(text: &str) -> Option<u64> {
+    let ticks = match text.strip_suffix('s') {
+        Some(seconds) => (seconds.parse::<f64>().ok()? * 20.0).round(),
+        None => text.trim_end_matches('t').parse::<f64>().ok()?,
+    };
+    (ticks >= 1.0 && ticks < u64::MAX as f64).then_some(ticks as u64)
+}
+
+/// Shows ticks as seconds, or just ticks if it is under a second
+fn format_ticks(ticks: u64) -> String {
+    if ticks < 20 {
+        format!("{ticks}t")
+    } else {
+        format!("{}s", ticks as f64 / 20.0)
+    }
+}
+
+/// Makes a table of the recipe on every tier from `base` to `top`
+fn table(
+    eut: u64,
+    duration: u64,
+    base: usize,
+    top: usize,
+    amps: u64,
+    max_parallel: u64,
+    perfect: bool,
+) -> String {
+    let rows = (base..=top)
+        .map(|tier| {
+            // Like GTCEu, parallels are done first at the base EU/t, then the power left
+            // over is used to overclock them all together
+            let budget = TIERS[tier].1.saturating_mul(amps);
+            let parallel = (budget / eut).clamp(1, max_parallel);
+            let Overclock {
+                overclocks,
+                eut,
+                duration,
+            } = overclock(eut.saturating_mul(parallel), duration, tier, perfect);
+            [
+                TIERS[tier].0.to_string(),
+                overclocks.to_string(),
+                eut.to_string(),
+                format_ticks(duration),
+                parallel.to_string(),
+                eut.div_ceil(TIERS[tier].1).to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let header = ["Tier", "OCs", "EU/t", "Time", "Par", "Amps"];
+    let widths = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|x| x[i].len())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(x, width)| format!("{x:<width$}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut text = line(&header.map(str::to_string));
+    for row in &rows {
+        text.push('\n');
+        text.push_str(&line(row));
+    }
+    text
+}
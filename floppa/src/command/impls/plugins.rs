@@ -0,0 +1,79 @@
+use serenity::{async_trait, model::channel::Message};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{
+        inner::CmdCtx, load_plugins, plugin_names, CommandMeta, ExtendedCommand, FlopMessagable,
+    },
+    sql::{FlopDB, FlopRole},
+    Cli, FlopResult,
+};
+
+#[derive(Debug)]
+pub struct PluginsCommand {
+    cli: Cli,
+}
+
+impl CommandMeta for PluginsCommand {
+    const ID: &'static str = "PluginsCommand";
+    const DESCRIPTION: &'static str = "Lists or reloads the loaded WASM plugins";
+}
+
+#[async_trait]
+impl ExtendedCommand for PluginsCommand {
+    fn construct(cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self { cli: cli.clone() })
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command).trim();
+        let names = match args {
+            "" => plugin_names(),
+            "reload" => {
+                if !db
+                    .read()
+                    .await
+                    .user_has_role(msg.author.id, &FlopRole::Admin)
+                {
+                    return Ok(FlopMessagable::Text(":clueless:".to_string()));
+                }
+                let cli = self.cli.clone();
+                match tokio::task::spawn_blocking(move || load_plugins(&cli)).await? {
+                    Ok(names) => names,
+                    Err(e) => {
+                        return Ok(FlopMessagable::Text(format!(
+                            "⚠️ Error reloading plugins: `{e}`"
+                        )))
+                    }
+                }
+            }
+            _ => {
+                return Ok(FlopMessagable::Text(format!(
+                    "Usage: `{0}` to list plugins or `{0} reload` to reload them",
+                    ctx.command
+                )))
+            }
+        };
+
+        if names.is_empty() {
+            return Ok(FlopMessagable::Text("No plugins are loaded".to_string()));
+        }
+        Ok(FlopMessagable::Text(format!(
+            "Loaded plugins:\n{}",
+            names
+                .iter()
+                .map(|x| format!("- `plugin:{x}`"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
@@ -0,0 +1,125 @@
+use serenity::{all::CreateAttachment, async_trait, model::channel::Message};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{
+        inner::{data_to_json, CmdCtx},
+        CommandMeta, ExtendedCommand, FlopMessagable,
+    },
+    sql::{CmdNode, FlopDB},
+    Cli, FlopResult,
+};
+
+use super::MessageCommand;
+
+/// The max length of a discord message
+const MESSAGE_LIMIT: usize = 2000;
+
+#[derive(Debug)]
+pub struct RawCommand;
+
+impl CommandMeta for RawCommand {
+    const ID: &'static str = "RawCommand";
+    const DESCRIPTION: &'static str = "Shows the source of a command";
+}
+
+#[async_trait]
+impl ExtendedCommand for RawCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let mut args = msg
+            .content
+            .trim_start_matches(ctx.command)
+            .split_whitespace();
+
+        let Some(mut name) = args.next() else {
+            return Ok(FlopMessagable::Text(format!(
+                "Usage {} [registry:](command)",
+                ctx.command
+            )));
+        };
+
+        let mut registry = ctx.registry;
+        if let Some((new_reg, new_name)) = name.split_once(':') {
+            registry = new_reg;
+            name = new_name;
+        }
+
+        let db_lock = db.read().await;
+        let canonicalised = db_lock
+            .canonicalise_command(registry.to_owned(), name.to_owned())
+            .await;
+
+        // Find the first command in the chain that exists, as the name may be inherited
+        let Some(cmd) = canonicalised
+            .stack
+            .iter()
+            .find_map(|(reg, name)| db_lock.get_command(reg.to_owned(), name.to_owned()))
+        else {
+            let end = if registry != ctx.registry {
+                format!(" in registry `{registry}`")
+            } else {
+                String::new()
+            };
+
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ Failed to find command `{name}`{end}"
+            )));
+        };
+
+        // Drop lock to free db for other uses
+        drop(db_lock);
+
+        let mut cmd_lock = cmd.lock().await;
+        let name = cmd_lock.get_name().to_owned();
+        let is_text = cmd_lock.get_type() == MessageCommand::ID;
+
+        let raw = match cmd_lock.get_node() {
+            CmdNode::Symlink { reg, name: dest } => {
+                return Ok(FlopMessagable::Text(format!(
+                    "`{name}` is a link to `{reg}:{dest}`"
+                )))
+            }
+            CmdNode::Subregistry(reg) => {
+                return Ok(FlopMessagable::Text(format!(
+                    "`{name}` is the registry `{reg}`"
+                )))
+            }
+            CmdNode::Cmd(cmd) => cmd.raw(),
+            CmdNode::Broken { data, .. } => data_to_json(data),
+        };
+        drop(cmd_lock);
+
+        let Some(raw) = raw else {
+            return Ok(FlopMessagable::Text(format!(
+                "`{name}` has no source to show"
+            )));
+        };
+
+        let (lang, ext) = if is_text {
+            ("", "txt")
+        } else {
+            ("json", "json")
+        };
+        let block = format!("```{lang}\n{raw}\n```");
+
+        // Code blocks cant be nested, so send those as files too, so they can be copied as is
+        if block.chars().count() > MESSAGE_LIMIT || raw.contains("```") {
+            Ok(CreateAttachment::bytes(raw.into_bytes(), format!("{name}.{ext}")).into())
+        } else {
+            Ok(FlopMessagable::Text(block))
+        }
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
@@ -0,0 +1,207 @@
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter, Timestamp},
+    async_trait,
+    model::channel::Message,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    schedule::Reminder,
+    sql::FlopDB,
+    stuff::{next_word, parse_spaced_duration, parse_time, truncate},
+    Cli, FlopResult,
+};
+
+/// How many reminders are shown on each page
+const PER_PAGE: usize = 10;
+/// The max length of a reminder, so it fits in a message with the mention and times
+const TEXT_LIMIT: usize = 1500;
+/// The max length of a reminder shown in the list
+const LIST_LIMIT: usize = 200;
+/// How many pending reminders each user can have
+const MAX_REMINDERS: usize = 25;
+
+#[derive(Debug)]
+pub struct RemindCommand;
+
+impl CommandMeta for RemindCommand {
+    const ID: &'static str = "RemindCommand";
+    const DESCRIPTION: &'static str = "Reminds you of something later, by reply or DM";
+}
+
+#[async_trait]
+impl ExtendedCommand for RemindCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command);
+        let (sub, rest) = next_word(args);
+
+        match sub {
+            "" => Ok(FlopMessagable::Text(usage(ctx.command))),
+            "list" => list(msg, db).await,
+            "cancel" => cancel(msg, db, rest).await.map(FlopMessagable::Text),
+            _ => add(msg, &ctx, db, args).await.map(FlopMessagable::Text),
+        }
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+fn usage(command: &str) -> String {
+    format!(
+        "Usage:```
+{command} [me] [dm] [in|at] <time> [to] <text>
+{command} list
+{command} cancel <id>```
+Times can be like `2h30m`, `5 minutes`, `14:30`, `2024-05-01 14:30` in UTC, or a discord timestamp"
+    )
+}
+
+/// Skips the first word if it is `word`
+fn skip_word<'a>(text: &'a str, word: &str) -> &'a str {
+    match next_word(text) {
+        (first, rest) if first.eq_ignore_ascii_case(word) => rest,
+        _ => text,
+    }
+}
+
+async fn add(
+    msg: &Message,
+    ctx: &CmdCtx<'_>,
+    db: &RwLock<FlopDB>,
+    args: &str,
+) -> FlopResult<String> {
+    let now = Timestamp::now().unix_timestamp();
+
+    let args = skip_word(args, "me");
+    let dm = next_word(args).0.eq_ignore_ascii_case("dm");
+    let args = skip_word(args, "dm");
+    let after_in = skip_word(args, "in");
+    let is_in = after_in.len() != args.len();
+    let args = skip_word(after_in, "at");
+
+    let (time, mut rest) = next_word(args);
+    let mut due = parse_time(time, now);
+    let (second, after_second) = next_word(rest);
+    if let Some(duration) = is_in.then(|| parse_spaced_duration(time, second)).flatten() {
+        // Durations can be given as a number and a unit, like `in 5 minutes`
+        due = now.checked_add(duration);
+        rest = after_second;
+    } else if let Some(both) = parse_time(&format!("{time}T{second}"), now) {
+        // Dates and times can be given as two words
+        due = Some(both);
+        rest = after_second;
+    }
+    let Some(due) = due else {
+        return Ok(format!(
+            "⚠️ `{time}` is not a valid time\n{}",
+            usage(ctx.command)
+        ));
+    };
+    if due <= now {
+        return Ok("⚠️ That time has already passed".to_string());
+    }
+
+    let text = skip_word(rest, "to").trim();
+    if text.is_empty() {
+        return Ok("⚠️ What should you be reminded of?".to_string());
+    }
+    if text.chars().count() > TEXT_LIMIT {
+        return Ok(format!(
+            "⚠️ Reminders can be at most {TEXT_LIMIT} characters long"
+        ));
+    }
+
+    let db = db.read().await;
+    if db.user_reminders(msg.author.id).await?.len() >= MAX_REMINDERS {
+        return Ok(format!(
+            "⚠️ You already have {MAX_REMINDERS} reminders, cancel some first"
+        ));
+    }
+
+    let mut reminder = Reminder {
+        id: 0,
+        user: msg.author.id,
+        guild: msg.guild_id,
+        channel: msg.channel_id,
+        message: msg.id,
+        text: text.to_owned(),
+        added: now,
+        due,
+        dm,
+    };
+    reminder.id = db.add_reminder(&reminder).await?;
+
+    Ok(format!(
+        "I'll remind you <t:{due}:R>, at <t:{due}:f> (#{})",
+        reminder.id
+    ))
+}
+
+async fn list(msg: &Message, db: &RwLock<FlopDB>) -> FlopResult<FlopMessagable> {
+    let reminders = db.read().await.user_reminders(msg.author.id).await?;
+
+    if reminders.is_empty() {
+        return Ok(FlopMessagable::Text(
+            "You have no pending reminders".to_string(),
+        ));
+    }
+
+    let count = reminders.len().div_ceil(PER_PAGE);
+    let pages = reminders
+        .chunks(PER_PAGE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let fields = chunk.iter().map(|x| {
+                let text = truncate(&x.text, LIST_LIMIT);
+                let dm = if x.dm { " by DM" } else { "" };
+                (
+                    format!("#{}", x.id),
+                    format!("<t:{}:R>{dm}\n{text}", x.due),
+                    false,
+                )
+            });
+            CreateEmbed::new()
+                .title("Your reminders")
+                .fields(fields)
+                .footer(CreateEmbedFooter::new(format!(
+                    "Page {}/{count}, {} reminders",
+                    i + 1,
+                    reminders.len()
+                )))
+                .into()
+        })
+        .collect();
+
+    Ok(FlopMessagable::Pages(pages))
+}
+
+async fn cancel(msg: &Message, db: &RwLock<FlopDB>, args: &str) -> FlopResult<String> {
+    let (id, _) = next_word(args);
+    let Ok(id) = id.trim_start_matches('#').parse::<i64>() else {
+        return Ok(format!("⚠️ `{id}` is not a reminder id"));
+    };
+
+    // Only the user's own reminders can be cancelled
+    if db
+        .read()
+        .await
+        .delete_reminder(id, Some(msg.author.id))
+        .await?
+    {
+        Ok(format!("Cancelled reminder #{id}"))
+    } else {
+        Ok(format!("⚠️ You have no reminder #{id}"))
+    }
+}
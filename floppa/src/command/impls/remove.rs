@@ -2,22 +2,31 @@ use serenity::{all::Mentionable, async_trait, model::channel::Message};
 use tokio::sync::RwLock;
 
 use crate::{
-    command::{inner::CmdCtx, ExtendedCommand, FlopMessagable},
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
     sql::{FlopDB, FlopRole},
     Cli, FlopResult,
 };
 
+use super::AttachmentCommand;
+
 #[derive(Debug)]
-pub struct RemoveCommand;
+pub struct RemoveCommand {
+    cli: Cli,
+}
+
+impl CommandMeta for RemoveCommand {
+    const ID: &'static str = "RemoveCommand";
+    const DESCRIPTION: &'static str = "Removes a command you own";
+}
 
 #[async_trait]
 impl ExtendedCommand for RemoveCommand {
-    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
-        Ok(Self)
+    fn construct(cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self { cli: cli.clone() })
     }
 
     async fn execute<'b>(
-        &mut self,
+        &self,
         msg: &Message,
         ctx: CmdCtx<'b>,
         db: &RwLock<FlopDB>,
@@ -40,13 +49,6 @@ impl ExtendedCommand for RemoveCommand {
             name = new_name;
         }
 
-        // Special case this command to not cause a mutex gridlock
-        if ctx.registry == registry && ctx.name == name.to_lowercase() {
-            return Ok(FlopMessagable::Text(
-                "Insufficent perms to remove this command".to_owned(),
-            ));
-        }
-
         let db_lock = db.read().await;
         let Some(cmd) = db_lock.get_command(registry.to_owned(), name.to_owned()) else {
             let end = if registry != ctx.registry {
@@ -64,11 +66,11 @@ impl ExtendedCommand for RemoveCommand {
         // Command can be deleted by the owner or the a RegMod
         let cmd_lock = cmd.lock().await;
         if cmd_lock.get_owner() != &msg.author.id
-            && !db_lock.user_has_role(msg.author.id, &FlopRole::RegMod(ctx.registry.to_owned()))
+            && !db_lock.user_has_role(msg.author.id, &FlopRole::RegMod(registry.to_owned()))
         {
             return Ok(FlopMessagable::Text(format!(
                 "⚠️ Cannot remove command, `{name}` is owned by {}",
-                cmd.lock().await.get_owner().mention()
+                cmd_lock.get_owner().mention()
             )));
         }
         // Drop lock on cmd to be able to delete it
@@ -82,6 +84,15 @@ impl ExtendedCommand for RemoveCommand {
             .remove_command(registry.to_owned(), name.to_owned())
             .await
         {
+            drop(db_lock);
+            // Stored files aren't needed once their command is gone
+            let mut cmd_lock = cmd.lock().await;
+            if cmd_lock.get_type() == AttachmentCommand::ID {
+                if let Some(data) = cmd_lock.get_node().save() {
+                    drop(cmd_lock);
+                    AttachmentCommand::remove_files(&self.cli, &data, db).await;
+                }
+            }
             Ok(FlopMessagable::Text(format!("Deleted command `{name}`")))
         } else {
             Ok(FlopMessagable::Text(format!(
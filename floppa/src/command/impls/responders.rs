@@ -0,0 +1,255 @@
+use std::time::Duration;
+
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter, Mentionable},
+    async_trait,
+    model::channel::Message,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    responder::{Responder, ResponderAction, Trigger},
+    sql::{FlopDB, FlopRole},
+    stuff::{next_word, parse_duration, truncate},
+    Cli, FlopResult,
+};
+
+/// How many responders are shown on each page
+const PER_PAGE: usize = 10;
+/// The max length of a trigger or body shown in the list
+const TEXT_LIMIT: usize = 200;
+
+#[derive(Debug)]
+pub struct RespondersCommand;
+
+impl CommandMeta for RespondersCommand {
+    const ID: &'static str = "RespondersCommand";
+    const DESCRIPTION: &'static str =
+        "Manages auto-responders, which react, reply or run a command when a message matches";
+}
+
+#[async_trait]
+impl ExtendedCommand for RespondersCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command);
+        let (sub, rest) = next_word(args);
+
+        if sub.is_empty() || sub == "list" {
+            return Ok(list(msg, db).await);
+        }
+
+        let mut db_lock = db.write().await;
+        if !db_lock.user_has_role(msg.author.id, &FlopRole::GlobalMod) {
+            return Ok(FlopMessagable::Text(":clueless:".to_string()));
+        }
+        let admin = db_lock.user_has_role(msg.author.id, &FlopRole::Admin);
+
+        let text = match sub {
+            "add" => add(msg, &mut db_lock, rest, admin).await?,
+            "cooldown" => {
+                let (id, rest) = next_word(rest);
+                let (time, _) = next_word(rest);
+                let cooldown = match time {
+                    "0" | "none" => Some(0),
+                    x => parse_duration(x),
+                };
+                match (find(msg, &db_lock, id, admin), cooldown) {
+                    (Err(e), _) => e,
+                    (_, None) => format!("⚠️ `{time}` is not a valid duration"),
+                    (Ok(id), Some(secs)) => {
+                        db_lock
+                            .set_responder_cooldown(id, Duration::from_secs(secs as u64))
+                            .await?;
+                        format!("Set the cooldown of responder #{id} to {secs} seconds")
+                    }
+                }
+            }
+            "count" => {
+                let (id, rest) = next_word(rest);
+                let counts = match next_word(rest).0 {
+                    "on" | "yes" | "true" => Some(true),
+                    "off" | "no" | "false" => Some(false),
+                    _ => None,
+                };
+                match (find(msg, &db_lock, id, admin), counts) {
+                    (Err(e), _) => e,
+                    // Only admins, as the counters are shared by everyone
+                    (_, _) if !admin => ":clueless:".to_string(),
+                    (_, None) => usage(ctx.command),
+                    (Ok(id), Some(counts)) => {
+                        db_lock.set_responder_counts(id, counts).await?;
+                        if counts {
+                            format!("Reactions from responder #{id} are now counted")
+                        } else {
+                            format!("Reactions from responder #{id} are no longer counted")
+                        }
+                    }
+                }
+            }
+            "delete" => match find(msg, &db_lock, next_word(rest).0, admin) {
+                Ok(id) => {
+                    db_lock.delete_responder(id).await?;
+                    format!("Deleted responder #{id}")
+                }
+                Err(e) => e,
+            },
+            _ => usage(ctx.command),
+        };
+        Ok(FlopMessagable::Text(text))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+fn usage(command: &str) -> String {
+    format!(
+        "Usage:```
+{command} list
+{command} add (global|guild|channel) (substring|word|regex) <trigger> (react|reply|command) <body>
+{command} cooldown <id> <duration>
+{command} count <id> (on|off)
+{command} delete <id>```
+Triggers with spaces can be wrapped in `\"` or `` ` ``, reactions are a list of emoji"
+    )
+}
+
+/// Splits off the first word, or everything in quotes or backticks if it starts with them
+fn next_quoted(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    for quote in ['"', '`'] {
+        if let Some(inner) = text.strip_prefix(quote) {
+            let end = inner.find(quote)?;
+            return Some((&inner[..end], &inner[end + 1..]));
+        }
+    }
+    Some(next_word(text))
+}
+
+async fn list(msg: &Message, db: &RwLock<FlopDB>) -> FlopMessagable {
+    let db_lock = db.read().await;
+    let responders = db_lock
+        .responders()
+        .iter()
+        .filter(|x| x.guild.is_none() || x.guild == msg.guild_id)
+        .cloned()
+        .collect::<Vec<_>>();
+    drop(db_lock);
+
+    if responders.is_empty() {
+        return FlopMessagable::Text("There are no responders here".to_string());
+    }
+
+    let count = responders.len().div_ceil(PER_PAGE);
+    let pages = responders
+        .chunks(PER_PAGE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let fields = chunk.iter().map(|x| {
+                let scope = match (x.guild, x.channel) {
+                    (_, Some(channel)) => channel.mention().to_string(),
+                    (Some(_), None) => "this server".to_string(),
+                    (None, None) => "everywhere".to_string(),
+                };
+                let cooldown = match x.cooldown.as_secs() {
+                    0 => String::new(),
+                    secs => format!(", {secs}s cooldown"),
+                };
+                let counts = if x.counts { ", counted" } else { "" };
+                (
+                    format!("#{}", x.id),
+                    format!(
+                        "{} `{}` → {} `{}`\nIn {scope}{cooldown}{counts}",
+                        x.trigger.kind(),
+                        truncate(x.trigger.pattern(), TEXT_LIMIT).replace('`', "'"),
+                        x.action.kind(),
+                        truncate(&x.action.body(), TEXT_LIMIT).replace('`', "'"),
+                    ),
+                    false,
+                )
+            });
+            CreateEmbed::new()
+                .title("Responders")
+                .fields(fields)
+                .footer(CreateEmbedFooter::new(format!(
+                    "Page {}/{count}, {} responders",
+                    i + 1,
+                    responders.len()
+                )))
+                .into()
+        })
+        .collect();
+
+    FlopMessagable::Pages(pages)
+}
+
+async fn add(msg: &Message, db: &mut FlopDB, args: &str, admin: bool) -> FlopResult<String> {
+    let (scope, rest) = next_word(args);
+    let (guild, channel) = match scope {
+        "global" if admin => (None, None),
+        "global" => return Ok("⚠️ Only admins can add global responders".to_string()),
+        "guild" | "server" => match msg.guild_id {
+            Some(guild) => (Some(guild), None),
+            None => return Ok("⚠️ This isn't a server".to_string()),
+        },
+        "channel" => (msg.guild_id, Some(msg.channel_id)),
+        _ => {
+            return Ok(format!(
+                "⚠️ Unknown scope `{scope}`, expected `global`, `guild` or `channel`"
+            ))
+        }
+    };
+
+    let (kind, rest) = next_word(rest);
+    let Some((pattern, rest)) = next_quoted(rest) else {
+        return Ok("⚠️ The trigger is missing its closing quote".to_string());
+    };
+    let trigger = match Trigger::parse(kind, pattern) {
+        Ok(trigger) => trigger,
+        Err(e) => return Ok(format!("⚠️ {e}")),
+    };
+
+    let (kind, body) = next_word(rest);
+    let action = match ResponderAction::parse(kind, body.trim()) {
+        Ok(action) => action,
+        Err(e) => return Ok(format!("⚠️ {e}")),
+    };
+
+    let responder = Responder::new(
+        0,
+        msg.author.id,
+        guild,
+        channel,
+        trigger,
+        action,
+        Duration::ZERO,
+    );
+    let id = db.add_responder(responder).await?;
+    Ok(format!("Added responder #{id}"))
+}
+
+/// Finds a responder that can be changed from here, or a message saying why not
+fn find(msg: &Message, db: &FlopDB, id: &str, admin: bool) -> Result<i64, String> {
+    let Ok(id) = id.trim_start_matches('#').parse::<i64>() else {
+        return Err(format!("⚠️ `{id}` is not a responder id"));
+    };
+    match db.responders().iter().find(|x| x.id == id) {
+        // Only admins can change global responders, or ones in other servers
+        Some(x) if !admin && (x.guild.is_none() || x.guild != msg.guild_id) => {
+            Err(format!("⚠️ Responder #{id} can't be changed from here"))
+        }
+        Some(_) => Ok(id),
+        None => Err(format!("⚠️ There is no responder #{id}")),
+    }
+}
@@ -2,7 +2,7 @@ use serenity::{all::Mentionable, async_trait, model::channel::Message};
 use tokio::sync::RwLock;
 
 use crate::{
-    command::{inner::CmdCtx, ExtendedCommand, FlopMessagable},
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
     sql::{FlopDB, FlopRole},
     stuff, Cli, FlopResult,
 };
@@ -10,6 +10,11 @@ use crate::{
 #[derive(Debug)]
 pub struct RoleCommand;
 
+impl CommandMeta for RoleCommand {
+    const ID: &'static str = "RoleCommand";
+    const DESCRIPTION: &'static str = "Gives or removes roles from users";
+}
+
 #[async_trait]
 impl ExtendedCommand for RoleCommand {
     fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
@@ -17,7 +22,7 @@ impl ExtendedCommand for RoleCommand {
     }
 
     async fn execute<'b>(
-        &mut self,
+        &self,
         msg: &Message,
         ctx: CmdCtx<'b>,
         db: &RwLock<FlopDB>,
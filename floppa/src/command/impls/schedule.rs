@@ -0,0 +1,293 @@
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter, Mentionable, Timestamp},
+    async_trait,
+    model::channel::Message,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    schedule::{Cron, Schedule, ScheduleAction},
+    sql::{FlopDB, FlopRole},
+    stuff::{next_word, parse_spaced_duration, parse_time, truncate},
+    Cli, FlopResult,
+};
+
+/// How many schedules are shown on each page
+const PER_PAGE: usize = 10;
+/// The max length of a schedule's body shown in the list
+const BODY_LIMIT: usize = 200;
+/// How many schedules each user can have
+const MAX_SCHEDULES: i64 = 10;
+
+#[derive(Debug)]
+pub struct ScheduleCommand;
+
+impl CommandMeta for ScheduleCommand {
+    const ID: &'static str = "ScheduleCommand";
+    const DESCRIPTION: &'static str =
+        "Adds, lists, pauses and deletes messages or commands sent at a time or on a cron schedule";
+}
+
+#[async_trait]
+impl ExtendedCommand for ScheduleCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        let args = msg.content.trim_start_matches(ctx.command);
+        let (sub, rest) = next_word(args);
+
+        let text = match sub {
+            "" | "list" => return list(msg, db).await,
+            "add" => add(msg, &ctx, db, rest).await?,
+            "pause" => set_paused(msg, db, rest, true).await?,
+            "resume" => set_paused(msg, db, rest, false).await?,
+            "delete" => delete(msg, db, rest).await?,
+            _ => usage(ctx.command),
+        };
+        Ok(FlopMessagable::Text(text))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+fn usage(command: &str) -> String {
+    format!(
+        "Usage:```
+{command} list
+{command} add (in <duration>|at <time>|cron <expression>) (message|command) <body>
+{command} pause <id>
+{command} resume <id>
+{command} delete <id>```
+Cron expressions are in UTC, like `0 9 * * mon-fri` or `@daily`"
+    )
+}
+
+async fn list(msg: &Message, db: &RwLock<FlopDB>) -> FlopResult<FlopMessagable> {
+    let schedules = db
+        .read()
+        .await
+        .list_schedules(msg.guild_id, msg.channel_id)
+        .await?;
+
+    if schedules.is_empty() {
+        return Ok(FlopMessagable::Text(
+            "There are no schedules here".to_string(),
+        ));
+    }
+
+    let count = schedules.len().div_ceil(PER_PAGE);
+    let pages = schedules
+        .chunks(PER_PAGE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let fields = chunk.iter().map(|x| {
+                let body = truncate(x.action.body(), BODY_LIMIT);
+                let repeat = match &x.cron {
+                    Some(cron) => format!("`{cron}`, next "),
+                    None => String::new(),
+                };
+                let paused = if x.paused { " (paused)" } else { "" };
+                (
+                    format!("#{}{paused}", x.id),
+                    format!(
+                        "{} in {} by {}, {repeat}<t:{}:R>```{}```",
+                        x.action.kind(),
+                        x.channel.mention(),
+                        x.owner.mention(),
+                        x.next_run,
+                        body.replace("```", "`\u{200b}``")
+                    ),
+                    false,
+                )
+            });
+            CreateEmbed::new()
+                .title("Schedules")
+                .fields(fields)
+                .footer(CreateEmbedFooter::new(format!(
+                    "Page {}/{count}, {} schedules",
+                    i + 1,
+                    schedules.len()
+                )))
+                .into()
+        })
+        .collect();
+
+    Ok(FlopMessagable::Pages(pages))
+}
+
+async fn add(
+    msg: &Message,
+    ctx: &CmdCtx<'_>,
+    db: &RwLock<FlopDB>,
+    args: &str,
+) -> FlopResult<String> {
+    // Schedules send things without anyone running a command, so need the same role as adding one
+    if !db
+        .read()
+        .await
+        .user_has_role(msg.author.id, &FlopRole::RegAdd(ctx.registry.to_owned()))
+    {
+        return Ok(":clueless:".to_string());
+    }
+
+    let now = Timestamp::now().unix_timestamp();
+    let (when, rest) = next_word(args);
+    let (time, rest) = next_word(rest);
+
+    let (next_run, cron, rest) = match when {
+        "in" | "at" => {
+            // Durations can be given as a number and a unit, like `in 5 minutes`
+            let (unit, after_unit) = next_word(rest);
+            let (next_run, rest) = match parse_spaced_duration(time, unit).filter(|_| when == "in")
+            {
+                Some(duration) => (now.checked_add(duration), after_unit),
+                None => (parse_time(time, now), rest),
+            };
+            match next_run {
+                Some(next_run) if next_run > now => (next_run, None, rest),
+                Some(_) => return Ok("⚠️ That time has already passed".to_string()),
+                None => return Ok(format!("⚠️ `{time}` is not a valid time")),
+            }
+        }
+        "cron" => {
+            // Macros are one word, anything else is the standard 5 fields
+            let (expr, rest) = if time.starts_with('@') {
+                (time.to_owned(), rest)
+            } else {
+                let mut expr = vec![time];
+                let mut rest = rest;
+                for _ in 0..4 {
+                    let (field, next) = next_word(rest);
+                    expr.push(field);
+                    rest = next;
+                }
+                (expr.join(" "), rest)
+            };
+            let cron = match Cron::parse(&expr) {
+                Ok(cron) => cron,
+                Err(e) => return Ok(format!("⚠️ Invalid cron expression: {e}")),
+            };
+            let Some(next_run) = cron.next_after(now) else {
+                return Ok(format!("⚠️ `{expr}` never runs"));
+            };
+            (next_run, Some(expr), rest)
+        }
+        _ => return Ok(usage(ctx.command)),
+    };
+
+    let (kind, body) = next_word(rest);
+    let body = body.trim();
+    if body.is_empty() {
+        return Ok(usage(ctx.command));
+    }
+    let action = match kind {
+        "message" => ScheduleAction::Message(body.to_owned()),
+        "command" => {
+            // Allow the command with or without the prefix
+            let body = body.strip_prefix(&ctx.cfg.prefix).unwrap_or(body);
+            ScheduleAction::Command(body.to_owned())
+        }
+        _ => return Ok(usage(ctx.command)),
+    };
+
+    let db = db.read().await;
+    if db.count_user_schedules(msg.author.id).await? >= MAX_SCHEDULES {
+        return Ok(format!(
+            "⚠️ You already have {MAX_SCHEDULES} schedules, delete some first"
+        ));
+    }
+
+    let mut schedule = Schedule {
+        id: 0,
+        owner: msg.author.id,
+        guild: msg.guild_id,
+        channel: msg.channel_id,
+        action,
+        cron,
+        next_run,
+        paused: false,
+    };
+    schedule.id = db.add_schedule(&schedule).await?;
+
+    Ok(format!(
+        "Added schedule #{}, it next runs <t:{next_run}:R>",
+        schedule.id
+    ))
+}
+
+/// Gets a schedule that the author is allowed to change, or a message saying why not
+async fn get_owned(msg: &Message, db: &FlopDB, args: &str) -> FlopResult<Result<Schedule, String>> {
+    let (id, _) = next_word(args);
+    let Ok(id) = id.trim_start_matches('#').parse::<i64>() else {
+        return Ok(Err(format!("⚠️ `{id}` is not a schedule id")));
+    };
+
+    let admin = db.user_has_role(msg.author.id, &FlopRole::Admin);
+    match db.get_schedule(id).await? {
+        Some(schedule) if schedule.guild != msg.guild_id && !admin => {
+            Ok(Err(format!("⚠️ Schedule #{id} is not in this server")))
+        }
+        Some(schedule) if schedule.owner != msg.author.id && !admin => Ok(Err(format!(
+            "⚠️ Schedule #{id} is owned by {}",
+            schedule.owner.mention()
+        ))),
+        Some(schedule) => Ok(Ok(schedule)),
+        None => Ok(Err(format!("⚠️ There is no schedule #{id}"))),
+    }
+}
+
+async fn set_paused(
+    msg: &Message,
+    db: &RwLock<FlopDB>,
+    args: &str,
+    paused: bool,
+) -> FlopResult<String> {
+    let db = db.read().await;
+    let schedule = match get_owned(msg, &db, args).await? {
+        Ok(schedule) => schedule,
+        Err(e) => return Ok(e),
+    };
+
+    if paused {
+        db.update_schedule(schedule.id, schedule.next_run, true)
+            .await?;
+        return Ok(format!("Paused schedule #{}", schedule.id));
+    }
+
+    // Repeating schedules skip the runs they missed while paused
+    let now = Timestamp::now().unix_timestamp();
+    let next_run = match &schedule.cron {
+        Some(cron) if schedule.next_run <= now => {
+            match Cron::parse(cron).ok().and_then(|x| x.next_after(now)) {
+                Some(next_run) => next_run,
+                None => return Ok(format!("⚠️ Schedule #{} never runs again", schedule.id)),
+            }
+        }
+        _ => schedule.next_run,
+    };
+    db.update_schedule(schedule.id, next_run, false).await?;
+    Ok(format!(
+        "Resumed schedule #{}, it next runs <t:{next_run}:R>",
+        schedule.id
+    ))
+}
+
+async fn delete(msg: &Message, db: &RwLock<FlopDB>, args: &str) -> FlopResult<String> {
+    let db = db.read().await;
+    let schedule = match get_owned(msg, &db, args).await? {
+        Ok(schedule) => schedule,
+        Err(e) => return Ok(e),
+    };
+    db.delete_schedule(schedule.id).await?;
+    Ok(format!("Deleted schedule #{}", schedule.id))
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rhai::{
+    module_resolvers::DummyModuleResolver,
+    packages::{Package, StandardPackage},
+    serde::{from_dynamic, to_dynamic},
+    Dynamic, Engine, Scope, AST,
+};
+use serde::{Deserialize, Serialize};
+use serenity::{async_trait, model::prelude::Message};
+use tracing::error;
+
+use crate::{
+    command::{inner::CmdCtx, Command, CommandMeta, FlopMessagable},
+    config::ScriptConfig,
+    Cli, FlopResult,
+};
+
+/// How deep function calls can go in a script
+const MAX_CALL_LEVELS: usize = 32;
+/// How deeply nested expressions can be, at global level then in functions
+const MAX_EXPR_DEPTHS: (usize, usize) = (64, 32);
+
+#[derive(Debug)]
+pub struct ScriptCommand {
+    data: Mutex<ScriptData>,
+    /// Held while the script runs, so runs changing the store don't overwrite each other
+    running: tokio::sync::Mutex<()>,
+    /// The compiled form of the source
+    ast: AST,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ScriptData {
+    /// The rhai source code of the script
+    source: String,
+    /// Values the script keeps between runs, exposed as the `store` map
+    #[serde(default)]
+    store: BTreeMap<String, serde_json::Value>,
+}
+
+/// Everything a script has access to when it runs
+#[derive(Debug, Serialize)]
+struct ScriptInput {
+    /// The text after the command, split on whitespace
+    args: Vec<String>,
+    /// The text after the command
+    text: String,
+    caller: ScriptCaller,
+    ctx: ScriptCtx,
+}
+
+#[derive(Debug, Serialize)]
+struct ScriptCaller {
+    id: String,
+    name: String,
+    mention: String,
+}
+
+/// The fields of [`CmdCtx`] scripts can see
+#[derive(Debug, Serialize)]
+struct ScriptCtx {
+    command: String,
+    registry: String,
+    name: String,
+    owner: String,
+    added: i64,
+}
+
+impl CommandMeta for ScriptCommand {
+    const ID: &'static str = "ScriptCommand";
+    const DESCRIPTION: &'static str = "Runs a sandboxed rhai script";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "source": { "type": "string", "minLength": 1 },
+                "store": { "type": "object" }
+            },
+            "required": ["source"]
+        })
+    }
+}
+
+#[async_trait]
+impl Command for ScriptCommand {
+    fn construct(_cli: &Cli, data: &[u8]) -> FlopResult<Self> {
+        let data: ScriptData = rmp_serde::from_slice(data)?;
+        // Compile once here so syntax errors show up when the command is added
+        // eval is a parse time symbol, so it has to be disabled here
+        let mut engine = Engine::new_raw();
+        engine
+            .set_max_expr_depths(MAX_EXPR_DEPTHS.0, MAX_EXPR_DEPTHS.1)
+            .disable_symbol("eval");
+        let ast = engine.compile(&data.source)?;
+        Ok(Self {
+            data: Mutex::new(data),
+            running: tokio::sync::Mutex::new(()),
+            ast,
+        })
+    }
+
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let text = msg.content.trim_start_matches(ctx.command).trim();
+        let input = ScriptInput {
+            args: text.split_whitespace().map(str::to_owned).collect(),
+            text: text.to_owned(),
+            caller: ScriptCaller {
+                id: msg.author.id.to_string(),
+                name: msg.author.name.clone(),
+                mention: format!("<@{}>", msg.author.id),
+            },
+            ctx: ScriptCtx {
+                command: ctx.command.to_owned(),
+                registry: ctx.registry.to_owned(),
+                name: ctx.name.to_owned(),
+                owner: ctx.owner.to_string(),
+                added: ctx.added,
+            },
+        };
+        let _running = self.running.lock().await;
+        let store = to_dynamic(&self.data.lock().unwrap_or_else(|e| e.into_inner()).store)?;
+        let cfg = ctx.cfg.scripting.clone();
+        let ast = self.ast.clone();
+
+        // Scripts are cpu bound, so keep them off the async workers
+        let result =
+            tokio::task::spawn_blocking(move || run_script(&cfg, &ast, input, store)).await?;
+
+        match result {
+            Ok((output, store)) => {
+                self.data.lock().unwrap_or_else(|e| e.into_inner()).store = store;
+                if output.is_empty() {
+                    Ok(FlopMessagable::_None)
+                } else {
+                    Ok(FlopMessagable::Text(output))
+                }
+            }
+            Err(e) => Ok(FlopMessagable::Text(format!("⚠️ Script error: ```{e}```"))),
+        }
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        match rmp_serde::to_vec_named(&*data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Error serialising script command: `{e}`");
+                None
+            }
+        }
+    }
+}
+
+/// Runs a script with the limits from the config
+///
+/// Returns what the script printed and returned, and the new store
+fn run_script(
+    cfg: &ScriptConfig,
+    ast: &AST,
+    input: ScriptInput,
+    store: Dynamic,
+) -> Result<(String, BTreeMap<String, serde_json::Value>), String> {
+    // Built from a raw engine so scripts can't import files or write to stderr
+    let mut engine = Engine::new_raw();
+    engine
+        .register_global_module(StandardPackage::new().as_shared_module())
+        .set_module_resolver(DummyModuleResolver::new())
+        .on_debug(|_, _, _| {})
+        .set_max_operations(cfg.max_operations)
+        .set_max_string_size(cfg.max_string_size)
+        .set_max_array_size(cfg.max_collection_size)
+        .set_max_map_size(cfg.max_collection_size)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTHS.0, MAX_EXPR_DEPTHS.1);
+
+    let start = Instant::now();
+    let timeout = Duration::from_millis(cfg.timeout_ms);
+    engine.on_progress(move |_| (start.elapsed() > timeout).then(|| "Script took too long".into()));
+
+    // Collect printed text as the output, stopping once its too long
+    let output = Arc::new(Mutex::new(String::new()));
+    let printed = output.clone();
+    let max_output = cfg.max_output;
+    engine.on_print(move |text| {
+        let mut lock = printed.lock().unwrap_or_else(|e| e.into_inner());
+        if lock.len() <= max_output {
+            lock.push_str(text);
+            lock.push('\n');
+        }
+    });
+
+    let mut scope = Scope::new();
+    scope.push_constant_dynamic("args", to_dynamic(&input.args).map_err(|e| e.to_string())?);
+    scope.push_constant("text", input.text);
+    scope.push_constant_dynamic(
+        "caller",
+        to_dynamic(&input.caller).map_err(|e| e.to_string())?,
+    );
+    scope.push_constant_dynamic("ctx", to_dynamic(&input.ctx).map_err(|e| e.to_string())?);
+    scope.push_dynamic("store", store);
+
+    let value = engine
+        .eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+        .map_err(|e| e.to_string())?;
+
+    let mut output = std::mem::take(&mut *output.lock().unwrap_or_else(|e| e.into_inner()));
+    if !value.is_unit() {
+        output += &value.to_string();
+    }
+    let output = output.trim_end().to_owned();
+    if output.len() > max_output {
+        return Err(format!("Output is longer than {max_output} bytes"));
+    }
+
+    let store = match scope.get("store") {
+        Some(store) => from_dynamic(store).map_err(|e| format!("Invalid store: {e}"))?,
+        None => BTreeMap::new(),
+    };
+
+    Ok((output, store))
+}
@@ -0,0 +1,56 @@
+use serenity::{async_trait, model::channel::Message};
+use tokio::sync::RwLock;
+
+use crate::{
+    command::{inner::CmdCtx, CommandMeta, ExtendedCommand, FlopMessagable},
+    shutdown::{ExitReason, SHUTDOWN},
+    sql::{FlopDB, FlopRole},
+    Cli, FlopResult,
+};
+
+#[derive(Debug)]
+pub struct ShutdownCommand;
+
+impl CommandMeta for ShutdownCommand {
+    const ID: &'static str = "ShutdownCommand";
+    const DESCRIPTION: &'static str =
+        "Stops the bot, or restarts it when ran as `restart` or with `restart`";
+}
+
+#[async_trait]
+impl ExtendedCommand for ShutdownCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'b>(
+        &self,
+        msg: &Message,
+        ctx: CmdCtx<'b>,
+        db: &RwLock<FlopDB>,
+    ) -> FlopResult<FlopMessagable> {
+        if !db
+            .read()
+            .await
+            .user_has_role(msg.author.id, &FlopRole::Admin)
+        {
+            return Ok(FlopMessagable::Text(":clueless:".to_string()));
+        }
+
+        let args = msg.content.trim_start_matches(ctx.command).trim();
+        let restart = ctx.name == "restart" || args == "restart";
+
+        // This only asks for shutdown, which waits for this command to reply
+        if restart {
+            SHUTDOWN.request(ExitReason::Restart);
+            Ok(FlopMessagable::Text("Restarting...".to_string()))
+        } else {
+            SHUTDOWN.request(ExitReason::Shutdown);
+            Ok(FlopMessagable::Text("Shutting down...".to_string()))
+        }
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
@@ -1,31 +1,38 @@
-use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 use serenity::{
     all::{CreateEmbed, Message},
     async_trait,
 };
-use stoik::{formula::Molecule, StoikError};
 use tracing::error;
 
 use crate::{
-    command::{CmdCtx, Command, FlopMessagable},
+    chem::{balance, format_side, side_counts, BalanceError, Species, CHARGE},
+    command::{CmdCtx, Command, CommandMeta, FlopMessagable},
     Cli, FlopResult,
 };
 
 const HELP_MSG: &str = "Usage: stoik [FLAGS] ... [EQUATION] ...
-Computes whether EQUATION is chemically balanced or not
+Computes whether EQUATION is chemically balanced or not, and how to balance it
+Ions are written with their charge after a ^, like Fe^3+ or SO4^2-, and e^- is an electron
   -h, --help Shows this";
 
 #[derive(Debug)]
 pub struct StoikCommand;
 
+impl CommandMeta for StoikCommand {
+    const ID: &'static str = "StoikCommand";
+    const DESCRIPTION: &'static str =
+        "Checks if chemical equations are balanced, and balances them";
+}
+
 #[async_trait]
 impl Command for StoikCommand {
     fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
         Ok(Self)
     }
 
-    async fn execute<'a>(&mut self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
         let args = msg.content.trim_start_matches(ctx.command).trim();
         let mut equation = String::new();
         for arg in args.split(char::is_whitespace) {
@@ -53,62 +60,69 @@ impl Command for StoikCommand {
         reactants_str = reactants_str.trim();
         products_str = products_str.trim();
 
-        let mut reactants = Vec::new();
-        for formula in reactants_str.split('+').map(|x| x.trim()) {
-            match Molecule::from_formula(formula) {
-                Ok(mol) => reactants.push((mol, formula.to_string())),
-                Err(e) => {
-                    return Ok(FlopMessagable::Text(format!(
-                        "```{}```",
-                        generate_stoik_error_msg(e, formula)
-                    )));
-                }
-            }
-        }
-
-        let mut products = Vec::new();
-        for formula in products_str.split('+').map(|x| x.trim()) {
-            match Molecule::from_formula(formula) {
-                Ok(mol) => products.push((mol, formula.to_string())),
-                Err(e) => {
-                    return Ok(FlopMessagable::Text(format!(
-                        "```{}```",
-                        generate_stoik_error_msg(e, formula)
-                    )));
-                }
-            }
-        }
+        let reactants = match parse_side(reactants_str) {
+            Ok(reactants) => reactants,
+            Err(e) => return Ok(FlopMessagable::Text(e)),
+        };
+        let products = match parse_side(products_str) {
+            Ok(products) => products,
+            Err(e) => return Ok(FlopMessagable::Text(e)),
+        };
 
-        let mut lhs = HashMap::new();
-        for mol in reactants {
-            extend_mol_map(&mut lhs, mol.0.get_map());
-        }
-
-        let mut rhs = HashMap::new();
-        for mol in products {
-            extend_mol_map(&mut rhs, mol.0.get_map());
-        }
+        let (Some(lhs), Some(rhs)) = (side_counts(&reactants), side_counts(&products)) else {
+            return Ok(FlopMessagable::Text(
+                "⚠️ The numbers in this reaction are too big to work with".to_string(),
+            ));
+        };
 
-        let mut keys = lhs.keys().collect::<HashSet<_>>();
-        let mut balanced = HashMap::new();
+        let mut keys = lhs.keys().collect::<BTreeSet<_>>();
         keys.extend(rhs.keys());
-
-        for key in keys {
-            balanced.insert(key.to_string(), lhs.get(key) == rhs.get(key));
-        }
+        let balanced = keys
+            .into_iter()
+            .map(|key| (key, lhs.get(key) == rhs.get(key)))
+            .collect::<BTreeMap<_, _>>();
 
         let is_balanced = balanced.values().all(|x| *x);
 
+        let names = balanced
+            .keys()
+            .map(|x| if *x == CHARGE { "Charge" } else { x.as_str() })
+            .fold(String::new(), |acc, x| format!("{acc}\n{x}"));
         let lhs_results = balanced
             .keys()
-            .map(|x| lhs.get(x).unwrap_or(&0))
+            .map(|x| lhs.get(*x).unwrap_or(&0))
             .fold(String::new(), |acc, x| format!("{acc}\n{x}"));
         let rhs_results = balanced
             .keys()
-            .map(|x| rhs.get(x).unwrap_or(&0))
+            .map(|x| rhs.get(*x).unwrap_or(&0))
             .fold(String::new(), |acc, x| format!("{acc}\n{x}"));
 
+        let solved = match balance(&reactants, &products) {
+            Ok(coefficients) => {
+                let (lhs, rhs) = coefficients.split_at(reactants.len());
+                format!(
+                    "```{} -> {}```",
+                    format_side(&reactants, lhs),
+                    format_side(&products, rhs)
+                )
+            }
+            Err(BalanceError::NoSolution) => {
+                "This reaction can't be balanced, check the formulas".to_string()
+            }
+            Err(BalanceError::Infinite) => {
+                "This is more than one independent reaction, so there are infinitely many ways to balance it"
+                    .to_string()
+            }
+            Err(BalanceError::WrongSide) => {
+                "This only balances with something moved to the other side".to_string()
+            }
+            Err(BalanceError::TooBig) => {
+                "The numbers in this reaction got too big to balance it".to_string()
+            }
+        };
+
         let embed = CreateEmbed::new()
+            .field("Element", format!("```{names}```"), true)
             .field("Reactants", format!("```{lhs_results}```"), true)
             .field("Products", format!("```{rhs_results}```"), true)
             .field(
@@ -116,12 +130,13 @@ impl Command for StoikCommand {
                 format!(
                     "```{}```",
                     balanced
-                        .iter()
-                        .map(|x| if *x.1 { "✅✅\n" } else { "❌❌\n" })
+                        .values()
+                        .map(|x| if *x { "✅✅\n" } else { "❌❌\n" })
                         .collect::<String>()
                 ),
                 true,
             )
+            .field("Balanced equation", solved, false)
             .title(if is_balanced {
                 "✅ Your reaction is balanced ✅"
             } else {
@@ -135,33 +150,24 @@ impl Command for StoikCommand {
     }
 }
 
-// maybe move to a pub func in stoik itself?
-fn generate_stoik_error_msg(e: StoikError, formula: &str) -> String {
-    match e {
-        StoikError::InvalidToken(loc) => {
-            loc.format_msg(formula, "Malformed formula", "Illegal token")
-        }
-        StoikError::NumberFirst(loc) => loc.format_msg(
-            formula,
-            "Malformed formula",
-            "Compound groups cannot start with numbers",
-        ),
-        StoikError::UnpairedParenthesis(loc) => {
-            loc.format_msg(formula, "Malformed formula", "Unpaired parenthesis")
-        }
-        StoikError::UnpairedBracket(loc) => {
-            loc.format_msg(formula, "Malformed formula", "Unpaired bracket")
-        }
-        e => e.to_string(),
-    }
-}
-
-fn extend_mol_map(main: &mut HashMap<String, i64>, mol: HashMap<String, i64>) {
-    for (key, mol_val) in mol {
-        if let Entry::Occupied(mut entry) = main.entry(key.clone()) {
-            *entry.get_mut() += mol_val;
-        } else {
-            main.insert(key, mol_val);
+/// Parses one side of an equation, errors are a message to show the user
+fn parse_side(side: &str) -> Result<Vec<Species>, String> {
+    // Charges like `^2+` have a plus in them, so dont split there
+    let mut species = Vec::new();
+    let mut start = 0;
+    let mut in_charge = false;
+    for (i, c) in side.char_indices() {
+        match c {
+            '^' => in_charge = true,
+            '+' if in_charge => in_charge = false,
+            '+' => {
+                species.push(Species::parse(&side[start..i])?);
+                start = i + 1;
+            }
+            c if in_charge && !c.is_ascii_digit() => in_charge = false,
+            _ => {}
         }
     }
+    species.push(Species::parse(&side[start..])?);
+    Ok(species)
 }
@@ -0,0 +1,78 @@
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter},
+    async_trait,
+    model::channel::Message,
+};
+
+use crate::{
+    command::{inner::CmdCtx, types, Command, CommandMeta, FlopMessagable},
+    Cli, FlopResult,
+};
+
+/// The max length of a schema shown in an embed field
+const SCHEMA_LIMIT: usize = 1000;
+
+#[derive(Debug)]
+pub struct TypesCommand;
+
+impl CommandMeta for TypesCommand {
+    const ID: &'static str = "TypesCommand";
+    const DESCRIPTION: &'static str = "Lists the command types that can be added";
+}
+
+#[async_trait]
+impl Command for TypesCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        Ok(Self)
+    }
+
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let filter = msg.content.trim_start_matches(ctx.command).trim();
+        let types = types()
+            .into_iter()
+            .filter(|x| filter.is_empty() || x.id.eq_ignore_ascii_case(filter))
+            .collect::<Vec<_>>();
+
+        if types.is_empty() {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ `{filter}` is not a valid command type"
+            )));
+        }
+
+        let count = types.len();
+        let pages = types
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let schema = match &ty.schema {
+                    Some(schema) => {
+                        let json = serde_json::to_string_pretty(schema).unwrap_or_default();
+                        if json.len() > SCHEMA_LIMIT {
+                            "Too long to show".to_owned()
+                        } else {
+                            format!("```json\n{json}```")
+                        }
+                    }
+                    None => "Any json".to_owned(),
+                };
+                CreateEmbed::new()
+                    .title(format!("`{}`", ty.id))
+                    .description(ty.description)
+                    .field("Data schema", schema, false)
+                    .field(
+                        "Usage",
+                        format!("`{}add (name) --[{}] [json data]`", ctx.cfg.prefix, ty.id),
+                        false,
+                    )
+                    .footer(CreateEmbedFooter::new(format!("Type {}/{count}", i + 1)))
+                    .into()
+            })
+            .collect();
+
+        Ok(FlopMessagable::Pages(pages))
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
@@ -1,7 +1,7 @@
 use serenity::{all::Message, async_trait};
 
 use crate::{
-    command::{CmdCtx, Command, FlopMessagable},
+    command::{CmdCtx, Command, CommandMeta, FlopMessagable},
     Cli, FlopResult,
 };
 
@@ -11,13 +11,18 @@ const GIT_VERSION: &str = env!("GIT_HASH");
 #[derive(Debug)]
 pub struct VersionCommand;
 
+impl CommandMeta for VersionCommand {
+    const ID: &'static str = "VersionCommand";
+    const DESCRIPTION: &'static str = "Shows the running version of the bot";
+}
+
 #[async_trait]
 impl Command for VersionCommand {
     fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
         Ok(Self)
     }
 
-    async fn execute<'a>(&mut self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
         let name = {
             if let Ok(current_user) = ctx
                 .ctx
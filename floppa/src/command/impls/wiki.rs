@@ -1,63 +1,260 @@
-use serenity::{async_trait, model::prelude::Message};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{CreateEmbed, CreateEmbedFooter},
+    async_trait,
+    model::prelude::Message,
+};
 use tracing::error;
 
 use crate::{
-    command::{inner::CmdCtx, Command, FlopMessagable},
+    command::{inner::CmdCtx, Command, CommandMeta, FlopMessagable},
+    http::host_allowed,
+    stuff::truncate,
     Cli, FlopResult,
 };
 
-const ENDPOINT: &str =
-    "https://en.wikipedia.org/w/api.php?action=query&format=json&list=search&srsearch=";
-const WIKI_PAGE: &str = "https://en.wikipedia.org/wiki/";
+const DEFAULT_API: &str = "https://en.wikipedia.org/w/api.php";
+const DEFAULT_NAME: &str = "Wikipedia";
 const NO_PAGE_MSG: &str = "Could not find page.";
+/// The most results that can be listed with `--n`
+const MAX_RESULTS: usize = 10;
+/// The max length of the extract shown for a single page
+const EXTRACT_LIMIT: usize = 1000;
+/// The max length of each extract shown in a list
+const LIST_EXTRACT_LIMIT: usize = 150;
+/// The max length of the query shown in a list title, which Discord caps at 256
+const TITLE_QUERY_LIMIT: usize = 200;
 
 #[derive(Debug)]
-pub struct WikiCommand;
+pub struct WikiCommand {
+    api: Url,
+    data: WikiData,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WikiData {
+    /// The `api.php` endpoint of the MediaWiki site, Wikipedia if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api: Option<String>,
+    /// The name of the wiki shown in responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+/// The parts of a MediaWiki query response that are used
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    query: Option<ApiQuery>,
+    error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiQuery {
+    #[serde(default)]
+    pages: Vec<ApiPage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    info: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiPage {
+    title: String,
+    /// Where the page was in the search results
+    #[serde(default)]
+    index: usize,
+    fullurl: Option<String>,
+    /// The start of the page as plain text, if the wiki has TextExtracts
+    extract: Option<String>,
+    /// The main image of the page, if the wiki has PageImages
+    thumbnail: Option<ApiThumbnail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiThumbnail {
+    source: String,
+}
+
+impl CommandMeta for WikiCommand {
+    const ID: &'static str = "WikiCommand";
+    const DESCRIPTION: &'static str = "Searches a MediaWiki wiki, Wikipedia by default";
+
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": ["object", "null"],
+            "properties": {
+                "api": { "type": "string", "minLength": 1 },
+                "name": { "type": "string", "minLength": 1 }
+            }
+        })
+    }
+}
 
 #[async_trait]
 impl Command for WikiCommand {
-    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
-        Ok(Self)
+    fn construct(_cli: &Cli, data: &[u8]) -> FlopResult<Self> {
+        let data: WikiData = if data.is_empty() {
+            WikiData::default()
+        } else {
+            rmp_serde::from_slice(data)?
+        };
+        let api = Url::parse(data.api.as_deref().unwrap_or(DEFAULT_API))?;
+        if !matches!(api.scheme(), "http" | "https") {
+            color_eyre::eyre::bail!("The wiki api must be a http or https url");
+        }
+        Ok(Self { api, data })
     }
 
-    async fn execute<'a>(&mut self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
-        // shittly URLEncode the message
-        let args = msg
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let mut count = None;
+        let mut words = Vec::new();
+        let mut args = msg
             .content
             .trim_start_matches(ctx.command)
-            .trim()
-            .replace(char::is_whitespace, "+");
-        if args.is_empty() {
+            .split_whitespace();
+        while let Some(arg) = args.next() {
+            match arg {
+                "--n" | "-n" => match args.next().and_then(|x| x.parse::<usize>().ok()) {
+                    Some(n) if (1..=MAX_RESULTS).contains(&n) => count = Some(n),
+                    _ => {
+                        return Ok(FlopMessagable::Text(format!(
+                            "⚠️ `{arg}` needs a number from 1 to {MAX_RESULTS} after it"
+                        )))
+                    }
+                },
+                x => words.push(x),
+            }
+        }
+        let query = words.join(" ");
+        if query.is_empty() {
             return Ok(FlopMessagable::Text(
                 "Missing argument for wiki lookup".to_owned(),
             ));
         }
 
-        // make a request to Wikipedia
-        let data_result = reqwest::get(format!("{ENDPOINT}{args}"))
-            .await?
-            .json::<serde_json::Value>()
-            .await;
+        // Only custom wikis are checked, Wikipedia is always allowed
+        if self.data.api.is_some() {
+            let host = self.api.host_str().unwrap_or_default();
+            if !host_allowed(&ctx.cfg.http.api_hosts, host) {
+                return Ok(FlopMessagable::Text(format!(
+                    "⚠️ `{host}` is not an allowed host"
+                )));
+            }
+        }
+
+        let limit = count.unwrap_or(1).to_string();
+        let url = Url::parse_with_params(
+            self.api.as_str(),
+            &[
+                ("action", "query"),
+                ("format", "json"),
+                ("formatversion", "2"),
+                ("generator", "search"),
+                ("gsrsearch", &query),
+                ("gsrlimit", &limit),
+                ("prop", "info|extracts|pageimages"),
+                ("inprop", "url"),
+                ("exintro", "1"),
+                ("explaintext", "1"),
+                ("exlimit", "max"),
+                ("piprop", "thumbnail"),
+                ("pithumbsize", "300"),
+            ],
+        )?;
 
+        // make a request to the wiki
+        let data_result = ctx.http.get_json::<ApiResponse>(url).await;
+        let name = self.data.name.as_deref().unwrap_or(DEFAULT_NAME);
         let data = match data_result {
             Ok(v) => v,
             Err(e) => {
-                error!("Failed to decode response: `{e}`");
+                error!("Failed to query `{}`: `{e}`", self.api);
                 return Ok(FlopMessagable::Text(format!(
-                    "Failed to decode response from Wikipedia:\n```{e}```"
+                    "Failed to get a response from {name}:\n```{e}```"
                 )));
             }
         };
+        if let Some(e) = data.error {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ {name} returned an error: `{}`",
+                e.info
+            )));
+        }
 
-        // Extract the title and send response
-        if let Some(title) = data["query"]["search"][0]["title"].as_str() {
-            Ok(FlopMessagable::Text(format!("{WIKI_PAGE}{title}")))
-        } else {
-            Ok(FlopMessagable::Text(NO_PAGE_MSG.to_string()))
+        let mut pages = data.query.map(|x| x.pages).unwrap_or_default();
+        pages.sort_by_key(|x| x.index);
+        let Some(first) = pages.first() else {
+            return Ok(FlopMessagable::Text(NO_PAGE_MSG.to_string()));
+        };
+
+        let footer = CreateEmbedFooter::new(name);
+        if count.is_none() {
+            let mut embed = CreateEmbed::new().title(&first.title).footer(footer);
+            if let Some(url) = self.page_url(first) {
+                embed = embed.url(url);
+            }
+            if let Some(extract) = first.extract.as_deref().filter(|x| !x.is_empty()) {
+                embed = embed.description(truncate(extract, EXTRACT_LIMIT));
+            }
+            if let Some(thumbnail) = &first.thumbnail {
+                embed = embed.thumbnail(&thumbnail.source);
+            }
+            return Ok(embed.into());
         }
+
+        let list = pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| {
+                let title = match self.page_url(page) {
+                    Some(url) => format!("[{}]({url})", page.title),
+                    None => page.title.clone(),
+                };
+                let extract = page
+                    .extract
+                    .as_deref()
+                    .and_then(|x| x.lines().next())
+                    .map(|x| format!("\n{}", truncate(x, LIST_EXTRACT_LIMIT)))
+                    .unwrap_or_default();
+                format!("{}. {title}{extract}", i + 1)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(CreateEmbed::new()
+            .title(format!(
+                "Results for {}",
+                truncate(&query, TITLE_QUERY_LIMIT)
+            ))
+            .description(list)
+            .footer(footer)
+            .into())
     }
 
     fn save(&self) -> Option<Vec<u8>> {
-        None
+        if self.data.api.is_none() && self.data.name.is_none() {
+            return None;
+        }
+        match rmp_serde::to_vec_named(&self.data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Error serialising wiki command: `{e}`");
+                None
+            }
+        }
+    }
+}
+
+impl WikiCommand {
+    /// The url of a page, made from the api url if the wiki didn't give one
+    fn page_url(&self, page: &ApiPage) -> Option<String> {
+        if let Some(url) = &page.fullurl {
+            return Some(url.clone());
+        }
+        let mut url = self.api.join("index.php").ok()?;
+        url.query_pairs_mut().append_pair("title", &page.title);
+        Some(url.into())
     }
 }
@@ -3,13 +3,20 @@ use std::fmt::Debug;
 use messagable::Messagable;
 use serenity::{
     async_trait,
-    builder::{CreateAllowedMentions, CreateEmbed, CreateMessage},
+    builder::{
+        CreateAllowedMentions, CreateAttachment, CreateEmbed, CreateInteractionResponseMessage,
+        CreateMessage,
+    },
     http::Http,
     model::prelude::*,
     prelude::*,
 };
 
-use crate::{sql::FlopDB, Cli, FlopResult};
+use tracing::error;
+
+use crate::{config::Config, http::HttpClient, sql::FlopDB, Cli, FlopResult};
+
+use super::pages;
 
 #[async_trait]
 pub trait Command: Debug {
@@ -19,16 +26,21 @@ pub trait Command: Debug {
         Self: Sized;
 
     /// Executes the command on the given Message event
-    async fn execute<'a>(&mut self, event: &Message, ctx: CmdCtx<'a>)
-        -> FlopResult<FlopMessagable>;
+    ///
+    /// The same command can be ran by many messages at once,
+    /// so any state it changes has to be behind its own lock
+    async fn execute<'a>(&self, event: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable>;
 
     /// Allows the command to serialise data to be asked
     /// Consumes the command, so it will be reinitalised
     fn save(&self) -> Option<Vec<u8>>;
 
-    // Gets the raw form of the Command
-    // TODO: epic macro to sealise src code at compile time
-    //fn raw(&self) -> &str;
+    /// Gets the raw form of the command's data, for people to copy and tweak
+    ///
+    /// By default this is the saved data decoded into json
+    fn raw(&self) -> Option<String> {
+        self.save().and_then(|data| data_to_json(&data))
+    }
 }
 
 #[async_trait]
@@ -40,8 +52,11 @@ pub trait ExtendedCommand: Debug {
         Self: Sized;
 
     /// Executes the command on the given Message event
+    ///
+    /// The same command can be ran by many messages at once,
+    /// so any state it changes has to be behind its own lock
     async fn execute<'a>(
-        &mut self,
+        &self,
         event: &Message,
         ctx: CmdCtx<'a>,
         data: &RwLock<FlopDB>,
@@ -50,9 +65,12 @@ pub trait ExtendedCommand: Debug {
     /// Allows the command to serialise data to be asked
     fn save(&self) -> Option<Vec<u8>>;
 
-    // Gets the raw form of the Command
-    // TODO: epic macro to sealise src code at compile time
-    //fn raw(&self) -> &str;
+    /// Gets the raw form of the command's data, for people to copy and tweak
+    ///
+    /// By default this is the saved data decoded into json
+    fn raw(&self) -> Option<String> {
+        self.save().and_then(|data| data_to_json(&data))
+    }
 }
 
 #[async_trait]
@@ -70,7 +88,7 @@ where
 
     /// Executes the command on the given Message event
     async fn execute<'a>(
-        &mut self,
+        &self,
         event: &Message,
         ctx: CmdCtx<'a>,
         _data: &RwLock<FlopDB>,
@@ -83,9 +101,54 @@ where
         <Self as Command>::save(self)
     }
 
-    // Gets the raw form of the Command
-    // TODO: epic macro to sealise src code at compile time
-    //fn raw(&self) -> &str;
+    /// Gets the raw form of the command's data, for people to copy and tweak
+    fn raw(&self) -> Option<String> {
+        <Self as Command>::raw(self)
+    }
+}
+
+/// Information about a command type, kept separate from the rust type so it can be renamed
+pub trait CommandMeta {
+    /// The id stored in the `type` column of the database
+    ///
+    /// This must never change once commands of this type have been added
+    const ID: &'static str;
+    /// A short description of what the command type does
+    const DESCRIPTION: &'static str;
+
+    /// A json schema for the data the command type is added with
+    ///
+    /// By default the command takes no data
+    fn schema() -> serde_json::Value {
+        serde_json::json!({ "type": "null" })
+    }
+}
+
+/// Encodes json from a user into msgpack command data, empty json gives no data
+///
+/// Errors are messages to show to the user
+pub fn json_to_data(json: &str) -> Result<Vec<u8>, String> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let value = serde_json::from_str::<rmpv::Value>(json)
+        .map_err(|e| format!("⚠️ Error deseralising json data: ```{e}```"))?;
+    rmp_serde::to_vec(&value).map_err(|e| format!("⚠️ Error seralising msgpack data: ```{e}```"))
+}
+
+/// Decodes msgpack command data into pretty printed json, if there is any
+pub fn data_to_json(data: &[u8]) -> Option<String> {
+    if data.is_empty() {
+        return None;
+    }
+    let value = match rmp_serde::from_slice::<rmpv::Value>(data) {
+        Ok(value) => value,
+        Err(e) => return Some(format!("⚠️ Could not decode data: {e}")),
+    };
+    match serde_json::to_string_pretty(&value) {
+        Ok(json) => Some(json),
+        Err(e) => Some(format!("⚠️ Could not display data: {e}")),
+    }
 }
 
 #[derive(Debug)]
@@ -103,6 +166,10 @@ pub struct CmdCtx<'a> {
     pub owner: UserId,
     /// When the command was added
     pub added: i64,
+    /// The global config
+    pub cfg: &'a Config,
+    /// The client for web requests, see [`HttpClient`]
+    pub http: &'a HttpClient,
 }
 
 /// Enum for return values of [`Command::execute`]
@@ -112,8 +179,16 @@ pub enum FlopMessagable {
     Text(String),
     /// Sends the list of embeds
     Embeds(Vec<CreateEmbed>),
+    /// Attaches the list of files
+    Attachments(Vec<CreateAttachment>),
     /// Stops the response from ping people and replies to the sender
     Response(MessageReference),
+    /// Applies all of these to the same message, in order
+    Combined(Vec<FlopMessagable>),
+    /// Sends the first page, with buttons to flip through the rest
+    ///
+    /// Each page has to fit in a single message
+    Pages(Vec<FlopMessagable>),
     /// There should be no reply from the bot
     _None,
 }
@@ -123,21 +198,126 @@ impl Messagable for FlopMessagable {
         match self {
             FlopMessagable::Text(s) => s.modify_message(builder),
             FlopMessagable::Embeds(e) => e.modify_message(builder),
+            FlopMessagable::Attachments(files) => builder.add_files(files),
             FlopMessagable::Response(msg) => builder
                 .allowed_mentions(CreateAllowedMentions::new().replied_user(false))
                 .reference_message(msg),
+            FlopMessagable::Combined(all) => all
+                .into_iter()
+                .fold(builder, |builder, x| x.modify_message(builder)),
+            FlopMessagable::Pages(pages) => {
+                let count = pages.len();
+                match pages.into_iter().next() {
+                    Some(first) => first
+                        .modify_message(builder)
+                        .components(pages::page_buttons(0, count)),
+                    None => builder,
+                }
+            }
             FlopMessagable::_None => builder,
         }
     }
 }
 
 impl FlopMessagable {
-    pub async fn send(self, msg: &Message, http: &Http) -> FlopResult<Message> {
-        let chain = self.chain(FlopMessagable::Response(msg.into()));
-        Ok(msg
-            .channel_id
-            .send_message(http, chain.apply_default())
-            .await?)
+    /// Sends this as a reply to `msg`, split over as many messages as needed
+    ///
+    /// Only the first message is a reply, and if a later part fails to send
+    /// the error is logged and the ones that were sent are returned
+    pub async fn send(self, msg: &Message, http: &Http) -> FlopResult<Vec<Message>> {
+        self.send_to(msg.channel_id, Some(msg), http).await
+    }
+
+    /// Sends this in a channel, optionally as a reply, see [`FlopMessagable::send`]
+    ///
+    /// Nobody is pinged either way, so text from users can't mention everyone
+    pub async fn send_to(
+        self,
+        channel: ChannelId,
+        reply: Option<&Message>,
+        http: &Http,
+    ) -> FlopResult<Vec<Message>> {
+        let mut sent = Vec::new();
+        for part in self.split() {
+            let builder = match reply {
+                Some(msg) if sent.is_empty() => part
+                    .chain(FlopMessagable::Response(msg.into()))
+                    .apply_default(),
+                _ => part
+                    .apply_default()
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            };
+
+            match channel.send_message(http, builder).await {
+                Ok(msg) => sent.push(msg),
+                Err(e) if sent.is_empty() => return Err(e.into()),
+                Err(e) => {
+                    error!(
+                        "Error sending part of a message in {}:```rust\n{e}```",
+                        channel.mention()
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Splits into parts that each fit within discord's limits for a single message
+    pub fn split(self) -> Vec<FlopMessagable> {
+        match self {
+            FlopMessagable::Text(s) => pages::split_text(&s, pages::MESSAGE_LIMIT)
+                .into_iter()
+                .map(FlopMessagable::Text)
+                .collect(),
+            FlopMessagable::Embeds(e) => pages::split_embeds(&e)
+                .into_iter()
+                .map(FlopMessagable::Embeds)
+                .collect(),
+            FlopMessagable::Attachments(files) => files
+                .chunks(pages::ATTACHMENT_LIMIT)
+                .map(|x| FlopMessagable::Attachments(x.to_vec()))
+                .collect(),
+            FlopMessagable::Combined(all) => {
+                // The nth part of each gets sent together in the nth message
+                let mut split = all
+                    .into_iter()
+                    .map(|x| x.split().into_iter())
+                    .collect::<Vec<_>>();
+                let mut parts = Vec::new();
+                loop {
+                    let part = split
+                        .iter_mut()
+                        .filter_map(Iterator::next)
+                        .collect::<Vec<_>>();
+                    if part.is_empty() {
+                        break parts;
+                    }
+                    parts.push(FlopMessagable::Combined(part));
+                }
+            }
+            other => vec![other],
+        }
+    }
+
+    /// Applies this to an interaction response, used to change pages
+    ///
+    /// References and nested pages dont make sense here, so they are ignored
+    pub fn modify_response(
+        self,
+        builder: CreateInteractionResponseMessage,
+    ) -> CreateInteractionResponseMessage {
+        match self {
+            FlopMessagable::Text(s) => builder.content(s),
+            FlopMessagable::Embeds(e) => builder.embeds(e),
+            FlopMessagable::Attachments(files) => builder.add_files(files),
+            FlopMessagable::Combined(all) => all
+                .into_iter()
+                .fold(builder, |builder, x| x.modify_response(builder)),
+            FlopMessagable::Response(_) | FlopMessagable::Pages(_) | FlopMessagable::_None => {
+                builder
+            }
+        }
     }
 
     pub const fn is_none(&self) -> bool {
@@ -163,6 +343,12 @@ impl From<Vec<CreateEmbed>> for FlopMessagable {
     }
 }
 
+impl From<CreateAttachment> for FlopMessagable {
+    fn from(value: CreateAttachment) -> Self {
+        FlopMessagable::Attachments(vec![value])
+    }
+}
+
 impl From<&Message> for FlopMessagable {
     fn from(msg: &Message) -> Self {
         FlopMessagable::Response(msg.into())
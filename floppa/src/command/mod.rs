@@ -1,6 +1,13 @@
 mod construct;
 mod impls;
 mod inner;
+mod pages;
+mod plugin;
+mod schema;
 
 pub use construct::*;
-pub use inner::{check_name, CmdCtx, Command, ExtendedCommand, FlopMessagable};
+pub use inner::{
+    check_name, json_to_data, CmdCtx, Command, CommandMeta, ExtendedCommand, FlopMessagable,
+};
+pub use pages::PageCache;
+pub use plugin::{load_plugins, plugin_names};
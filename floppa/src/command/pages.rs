@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use serenity::{
+    all::{
+        ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed,
+        CreateInteractionResponse, CreateInteractionResponseMessage,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+use tracing::error;
+
+use super::FlopMessagable;
+
+/// The max length of a discord message
+pub const MESSAGE_LIMIT: usize = 2000;
+/// The max amount of embeds in a discord message
+pub const EMBED_LIMIT: usize = 10;
+/// The max amount of text across all the embeds in a discord message
+pub const EMBED_TEXT_LIMIT: usize = 6000;
+/// The max amount of files in a discord message
+pub const ATTACHMENT_LIMIT: usize = 10;
+/// How many paginated messages are kept around to be flipped through
+const PAGE_CACHE_SIZE: usize = 128;
+
+/// What closes a code block that is split across chunks
+const FENCE_CLOSE: &str = "\n```";
+/// The longest language kept when a split code block is reopened
+const FENCE_LANG_LIMIT: usize = 16;
+/// The room kept in each chunk for reopening and closing a split code block
+const FENCE_RESERVE: usize = 3 + FENCE_LANG_LIMIT + 1 + FENCE_CLOSE.len();
+
+const PREV_ID: &str = "flop_page_prev";
+const NEXT_ID: &str = "flop_page_next";
+const COUNT_ID: &str = "flop_page_count";
+
+/// Splits embeds into groups that each fit in one message, by count and by total text
+pub fn split_embeds(embeds: &[CreateEmbed]) -> Vec<Vec<CreateEmbed>> {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    let mut current_len = 0;
+    for embed in embeds {
+        let len = embed_text_len(embed);
+        if !current.is_empty()
+            && (current.len() >= EMBED_LIMIT || current_len + len > EMBED_TEXT_LIMIT)
+        {
+            groups.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        current.push(embed.clone());
+        current_len += len;
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
+/// Counts the text in an embed the way discord does for [`EMBED_TEXT_LIMIT`]
+fn embed_text_len(embed: &CreateEmbed) -> usize {
+    let Ok(json) = serde_json::to_value(embed) else {
+        return 0;
+    };
+    let len =
+        |x: Option<&serde_json::Value>| x.and_then(|x| x.as_str()).map_or(0, |x| x.chars().count());
+    let fields = json["fields"].as_array().map_or(0, |fields| {
+        fields
+            .iter()
+            .map(|x| len(x.get("name")) + len(x.get("value")))
+            .sum()
+    });
+    len(json.get("title"))
+        + len(json.get("description"))
+        + len(json["footer"].get("text"))
+        + len(json["author"].get("name"))
+        + fields
+}
+
+/// Splits text into chunks under `limit` chars, preferring newlines then whitespace
+///
+/// Code blocks that are split are closed at the end of a chunk and reopened in the next
+pub fn split_text(text: &str, limit: usize) -> Vec<String> {
+    if text.chars().count() <= limit {
+        return vec![text.to_owned()];
+    }
+
+    // Every piece fits in a chunk along with reopening and closing a block around it
+    let pieces = text
+        .split('\n')
+        .flat_map(|line| {
+            let pieces = split_line(line, limit.saturating_sub(FENCE_RESERVE).max(1));
+            // Only whole lines can open or close a block
+            let toggles = line.matches("```").count() % 2 == 1;
+            let last = pieces.len() - 1;
+            pieces
+                .into_iter()
+                .enumerate()
+                .map(move |(i, x)| (x, toggles && i == last))
+        })
+        .collect::<Vec<_>>();
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+    // What reopens the code block we are in, if any
+    let mut fence: Option<String> = None;
+
+    let mut pieces = pieces.into_iter().peekable();
+    while let Some((piece, toggles)) = pieces.next() {
+        let piece_len = piece.chars().count();
+        // A block that is left open needs room to be closed, and one that is opened needs
+        // room for its first line too, so it isn't left empty at the end of a chunk
+        let needed = match (fence.is_some(), toggles) {
+            (true, false) => piece_len + FENCE_CLOSE.len(),
+            (false, true) => {
+                piece_len
+                    + pieces.peek().map_or(0, |(next, closes)| {
+                        let close = if *closes { 0 } else { FENCE_CLOSE.len() };
+                        1 + next.chars().count() + close
+                    })
+            }
+            _ => piece_len,
+        };
+
+        if current_len > 0 && current_len + 1 + needed > limit {
+            if fence.is_some() {
+                current.push_str(FENCE_CLOSE);
+            }
+            chunks.push(std::mem::take(&mut current));
+            current_len = 0;
+            if let Some(open) = &fence {
+                if toggles && piece.trim() == "```" {
+                    // The block was already closed at the end of the last chunk
+                    fence = None;
+                    continue;
+                }
+                current.push_str(open);
+                current_len = open.chars().count();
+            }
+        }
+        if current_len > 0 {
+            current.push('\n');
+            current_len += 1;
+        }
+        current.push_str(&piece);
+        current_len += piece_len;
+
+        if toggles {
+            fence = match fence {
+                Some(_) => None,
+                None => Some(reopen_fence(&piece)),
+            };
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// The fence that reopens a block opened by `line`, keeping its language if it is short
+fn reopen_fence(line: &str) -> String {
+    let lang = line.rsplit("```").next().unwrap_or_default().trim();
+    if lang.is_empty() || lang.contains(char::is_whitespace) || lang.len() > FENCE_LANG_LIMIT {
+        "```".to_owned()
+    } else {
+        format!("```{lang}")
+    }
+}
+
+/// Splits a single line into pieces under `limit` chars, at whitespace if possible
+fn split_line(line: &str, limit: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.chars().count() > limit {
+        let cut = rest
+            .char_indices()
+            .nth(limit)
+            .map_or(rest.len(), |(i, _)| i);
+        let split_at = rest[..cut]
+            .rfind(char::is_whitespace)
+            .filter(|x| *x > 0)
+            .unwrap_or(cut);
+        pieces.push(rest[..split_at].to_owned());
+        rest = rest[split_at..].trim_start();
+    }
+    pieces.push(rest.to_owned());
+    pieces
+}
+
+/// The buttons shown under a paginated message
+pub fn page_buttons(page: usize, count: usize) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(PREV_ID)
+            .label("◀")
+            .style(ButtonStyle::Secondary)
+            .disabled(page == 0),
+        CreateButton::new(COUNT_ID)
+            .label(format!("{}/{count}", page + 1))
+            .style(ButtonStyle::Secondary)
+            .disabled(true),
+        CreateButton::new(NEXT_ID)
+            .label("▶")
+            .style(ButtonStyle::Secondary)
+            .disabled(page + 1 >= count),
+    ])]
+}
+
+#[derive(Debug)]
+struct PageState {
+    /// Who asked for the pages, only they can flip through them
+    user: UserId,
+    pages: Vec<FlopMessagable>,
+    current: usize,
+}
+
+#[derive(Debug, Default)]
+/// Keeps track of sent paginated messages, so their buttons can be handled
+pub struct PageCache {
+    messages: RwLock<HashMap<MessageId, PageState>>,
+}
+
+impl PageCache {
+    /// Starts tracking a paginated message that was sent on behalf of `user`
+    pub async fn insert(&self, msg: MessageId, user: UserId, pages: Vec<FlopMessagable>) {
+        let mut lock = self.messages.write().await;
+        lock.insert(
+            msg,
+            PageState {
+                user,
+                pages,
+                current: 0,
+            },
+        );
+        if lock.len() > PAGE_CACHE_SIZE {
+            if let Some(oldest) = lock.keys().min().copied() {
+                lock.remove(&oldest);
+            }
+        }
+    }
+
+    /// Handles a button press on a paginated message, ignoring other interactions
+    pub async fn handle(&self, ctx: &Context, interaction: &ComponentInteraction) {
+        let step: isize = match interaction.data.custom_id.as_str() {
+            PREV_ID => -1,
+            NEXT_ID => 1,
+            _ => return,
+        };
+
+        let mut lock = self.messages.write().await;
+        let response = match lock.get_mut(&interaction.message.id) {
+            None => CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content("These pages have expired, run the command again")
+                    .ephemeral(true),
+            ),
+            Some(state) if state.user != interaction.user.id => CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("Only {} can change the page", state.user.mention()))
+                    .ephemeral(true),
+            ),
+            Some(state) => {
+                state.current = state
+                    .current
+                    .saturating_add_signed(step)
+                    .min(state.pages.len() - 1);
+                let page = state.pages[state.current].clone();
+                let builder = CreateInteractionResponseMessage::new()
+                    .content("")
+                    .embeds(Vec::new())
+                    .components(page_buttons(state.current, state.pages.len()));
+                CreateInteractionResponse::UpdateMessage(page.modify_response(builder))
+            }
+        };
+        drop(lock);
+
+        if let Err(e) = interaction.create_response(&ctx.http, response).await {
+            error!("Error changing page```rust\n{e}```");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The words outside of fences, which splitting shouldn't lose or reorder
+    fn words(text: &str) -> Vec<&str> {
+        text.split('\n')
+            .filter(|x| !x.trim_start().starts_with("```"))
+            .flat_map(str::split_whitespace)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_short_text() {
+        assert_eq!(split_text("a\nb", 40), ["a\nb"]);
+    }
+
+    #[test]
+    fn prefers_newlines() {
+        let line = "x".repeat(15);
+        let text = [line.as_str(); 4].join("\n");
+        assert_eq!(
+            split_text(&text, 40),
+            [format!("{line}\n{line}"), format!("{line}\n{line}")]
+        );
+    }
+
+    #[test]
+    fn splits_long_lines_at_whitespace() {
+        let text = "word ".repeat(20);
+        let chunks = split_text(text.trim_end(), 40);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|x| x.chars().count() <= 40));
+        assert!(chunks
+            .iter()
+            .all(|x| !x.starts_with(' ') && !x.ends_with(' ')));
+        assert_eq!(words(&chunks.join("\n")), words(&text));
+    }
+
+    #[test]
+    fn reopens_code_blocks() {
+        let text = format!("```rust\n{}\n```", ["let x = 1;"; 8].join("\n"));
+        let chunks = split_text(&text, 50);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.starts_with("```rust\n"), "{chunk:?}");
+            assert!(chunk.ends_with("\n```"), "{chunk:?}");
+        }
+        assert_eq!(words(&chunks.join("\n")), words(&text));
+    }
+
+    #[test]
+    fn leaves_room_to_close_an_opened_block() {
+        // The fence only just fits after the text, so it has to move to the next chunk
+        let text = format!("{}\n```\n{}\n```", "x".repeat(30), "y".repeat(30));
+        let chunks = split_text(&text, 60);
+        assert_eq!(
+            chunks,
+            ["x".repeat(30), format!("```\n{}\n```", "y".repeat(30))]
+        );
+    }
+
+    #[test]
+    fn stays_under_the_limit() {
+        // A small xorshift, so the text is the same every run
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = |n: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % n
+        };
+
+        for limit in [40, 100, 2000] {
+            for _ in 0..100 {
+                let mut lines = Vec::new();
+                let mut open = false;
+                for _ in 0..next(60) {
+                    // Blocks are never empty, so any empty block would come from splitting
+                    if next(6) == 0 && lines.last().is_some_and(|x: &String| !x.starts_with("```"))
+                    {
+                        lines.push(if open { "```" } else { "```rust" }.to_owned());
+                        open = !open;
+                    } else {
+                        let len = next(limit as u64 * 2) as usize;
+                        let line = (0..len)
+                            .map(|i| if i % 7 == 6 { ' ' } else { 'a' })
+                            .collect::<String>();
+                        lines.push(format!("{line}b"));
+                    }
+                }
+                let text = lines.join("\n");
+
+                let chunks = split_text(&text, limit);
+                for chunk in &chunks {
+                    assert!(chunk.chars().count() <= limit, "{chunk:?}");
+                    assert!(!chunk.contains("```rust\n```"), "{chunk:?}");
+                    assert!(!chunk.contains("```\n```"), "{chunk:?}");
+                }
+                assert_eq!(words(&chunks.join("\n")), words(&text));
+            }
+        }
+    }
+
+    #[test]
+    fn splits_embeds_by_count() {
+        let embeds = vec![CreateEmbed::new().title("a"); EMBED_LIMIT + 2];
+        let groups = split_embeds(&embeds);
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [10, 2]);
+    }
+
+    #[test]
+    fn splits_embeds_by_text() {
+        let embed = CreateEmbed::new()
+            .title("a".repeat(100))
+            .description("b".repeat(2000))
+            .field("c".repeat(50), "d".repeat(350), false)
+            .footer(serenity::all::CreateEmbedFooter::new("e".repeat(20)));
+        assert_eq!(embed_text_len(&embed), 2520);
+
+        let groups = split_embeds(&vec![embed; 5]);
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+    }
+}
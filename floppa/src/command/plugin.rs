@@ -0,0 +1,297 @@
+//! Command types loaded at runtime from WASM modules in the `plugins` folder of the run dir
+//!
+//! Each `name.wasm` file registers the command type `plugin:name`
+//!
+//! ## ABI
+//!
+//! A plugin module has to export:
+//! - `memory`, its linear memory
+//! - `floppa_alloc(len: i32) -> i32`, which allocates `len` bytes and returns a pointer to them
+//! - `floppa_execute(ptr: i32, len: i32) -> i64`, which runs the command
+//!
+//! `floppa_execute` is given a pointer to a UTF-8 json object that was written into memory
+//! allocated with `floppa_alloc`, in the form:
+//! ```json
+//! {
+//!     "args": "text after the command",
+//!     "caller": { "id": "1234", "name": "someone" },
+//!     "ctx": { "command": "$cmd", "registry": "root", "name": "cmd", "owner": "1234", "added": 0 },
+//!     "data": null
+//! }
+//! ```
+//! where `data` is the json data the command was added with, or last returned.
+//!
+//! It returns the pointer to the UTF-8 json output in the upper 32 bits,
+//! and its length in the lower 32 bits. The output is in the form:
+//! ```json
+//! { "text": "optional reply", "data": "optional new data to save" }
+//! ```
+//!
+//! No imports are provided, and plugins are limited in fuel and memory by [`PluginConfig`]
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::{async_trait, model::prelude::Message};
+use tracing::{error, info};
+use wasmi::{Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::{config::PluginConfig, Cli, FlopResult};
+
+use super::{inner::CmdCtx, Command, FlopMessagable};
+
+/// The prefix of the command type of all plugins
+pub const PLUGIN_PREFIX: &str = "plugin:";
+/// The folder in the run dir that plugins are loaded from
+const PLUGIN_DIR: &str = "plugins";
+
+/// All the currently loaded plugins, by name
+static PLUGINS: RwLock<BTreeMap<String, Arc<Plugin>>> = RwLock::new(BTreeMap::new());
+
+#[derive(Debug)]
+struct Plugin {
+    engine: Engine,
+    module: Module,
+}
+
+/// (Re)loads all the plugins in the plugin dir, replacing the ones currently loaded
+///
+/// Returns the names of the plugins that were loaded
+pub fn load_plugins(cli: &Cli) -> FlopResult<Vec<String>> {
+    let dir = cli.get_path(PLUGIN_DIR);
+    let mut plugins = BTreeMap::new();
+
+    if dir.is_dir() {
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|x| x == "wasm") {
+                let Some(name) = path.file_stem().and_then(|x| x.to_str()) else {
+                    continue;
+                };
+                let module = match Module::new(&engine, &std::fs::read(&path)?[..]) {
+                    Ok(module) => module,
+                    Err(e) => {
+                        error!("Error loading plugin `{name}`: `{e}`");
+                        continue;
+                    }
+                };
+                plugins.insert(
+                    name.to_owned(),
+                    Arc::new(Plugin {
+                        engine: engine.clone(),
+                        module,
+                    }),
+                );
+            }
+        }
+    }
+
+    let names = plugins.keys().cloned().collect::<Vec<_>>();
+    info!("Loaded {} plugins", names.len());
+    *PLUGINS.write().unwrap_or_else(|e| e.into_inner()) = plugins;
+    Ok(names)
+}
+
+/// Checks if a command type is a plugin that is loaded
+pub fn plugin_exists(ty: &str) -> bool {
+    ty.strip_prefix(PLUGIN_PREFIX).is_some_and(|name| {
+        PLUGINS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(name)
+    })
+}
+
+/// Gets the names of all the loaded plugins
+pub fn plugin_names() -> Vec<String> {
+    PLUGINS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+fn get_plugin(name: &str) -> Option<Arc<Plugin>> {
+    PLUGINS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()
+}
+
+/// A command that runs a plugin
+///
+/// The plugin is looked up each time, so reloading plugins changes existing commands,
+/// and commands for plugins that aren't loaded keep their data
+#[derive(Debug)]
+pub struct PluginCommand {
+    name: String,
+    data: Mutex<serde_json::Value>,
+    /// Held while the plugin runs, so runs changing the data don't overwrite each other
+    running: tokio::sync::Mutex<()>,
+}
+
+impl PluginCommand {
+    /// Constructs a command for the plugin type `ty`
+    pub fn new(ty: &str, data: &[u8]) -> FlopResult<Self> {
+        let data = if data.is_empty() {
+            serde_json::Value::Null
+        } else {
+            rmp_serde::from_slice(data)?
+        };
+        Ok(Self {
+            name: ty.trim_start_matches(PLUGIN_PREFIX).to_owned(),
+            data: Mutex::new(data),
+            running: tokio::sync::Mutex::new(()),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PluginInput<'a> {
+    args: &'a str,
+    caller: PluginCaller,
+    ctx: PluginCtx<'a>,
+    data: &'a serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct PluginCaller {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PluginCtx<'a> {
+    command: &'a str,
+    registry: &'a str,
+    name: &'a str,
+    owner: String,
+    added: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginOutput {
+    text: Option<String>,
+    data: Option<serde_json::Value>,
+}
+
+#[async_trait]
+impl Command for PluginCommand {
+    fn construct(_cli: &Cli, _data: &[u8]) -> FlopResult<Self> {
+        color_eyre::eyre::bail!("Plugin commands are constructed from their type")
+    }
+
+    async fn execute<'a>(&self, msg: &Message, ctx: CmdCtx<'a>) -> FlopResult<FlopMessagable> {
+        let Some(plugin) = get_plugin(&self.name) else {
+            return Ok(FlopMessagable::Text(format!(
+                "⚠️ The plugin `{}` is not loaded",
+                self.name
+            )));
+        };
+
+        let _running = self.running.lock().await;
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let input = serde_json::to_vec(&PluginInput {
+            args: msg.content.trim_start_matches(ctx.command).trim(),
+            caller: PluginCaller {
+                id: msg.author.id.to_string(),
+                name: msg.author.name.clone(),
+            },
+            ctx: PluginCtx {
+                command: ctx.command,
+                registry: ctx.registry,
+                name: ctx.name,
+                owner: ctx.owner.to_string(),
+                added: ctx.added,
+            },
+            data: &data,
+        })?;
+        let cfg = ctx.cfg.plugins.clone();
+
+        // Plugins are cpu bound, so keep them off the async workers
+        let result = tokio::task::spawn_blocking(move || run_plugin(&plugin, &cfg, &input)).await?;
+
+        match result {
+            Ok(output) => {
+                if let Some(data) = output.data {
+                    *self.data.lock().unwrap_or_else(|e| e.into_inner()) = data;
+                }
+                Ok(output
+                    .text
+                    .filter(|x| !x.is_empty())
+                    .map_or(FlopMessagable::_None, FlopMessagable::Text))
+            }
+            Err(e) => Ok(FlopMessagable::Text(format!("⚠️ Plugin error: ```{e}```"))),
+        }
+    }
+
+    fn save(&self) -> Option<Vec<u8>> {
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        if data.is_null() {
+            return None;
+        }
+        match rmp_serde::to_vec_named(&*data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Error serialising plugin command: `{e}`");
+                None
+            }
+        }
+    }
+}
+
+/// Runs a plugin in a new instance, following the ABI in the module docs
+fn run_plugin(plugin: &Plugin, cfg: &PluginConfig, input: &[u8]) -> Result<PluginOutput, String> {
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(cfg.max_memory)
+        .instances(1)
+        .build();
+    let mut store = Store::new(&plugin.engine, limits);
+    store.limiter(|limits: &mut StoreLimits| limits);
+    store.set_fuel(cfg.fuel).map_err(|e| e.to_string())?;
+
+    let linker = Linker::<StoreLimits>::new(&plugin.engine);
+    let instance = linker
+        .instantiate(&mut store, &plugin.module)
+        .and_then(|x| x.start(&mut store))
+        .map_err(|e| e.to_string())?;
+
+    let memory = instance
+        .get_memory(&store, "memory")
+        .ok_or("Plugin does not export `memory`")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&store, "floppa_alloc")
+        .map_err(|e| e.to_string())?;
+    let execute = instance
+        .get_typed_func::<(i32, i32), i64>(&store, "floppa_execute")
+        .map_err(|e| e.to_string())?;
+
+    let len = i32::try_from(input.len()).map_err(|e| e.to_string())?;
+    let ptr = alloc.call(&mut store, len).map_err(|e| e.to_string())?;
+    memory
+        .write(&mut store, ptr as u32 as usize, input)
+        .map_err(|e| e.to_string())?;
+
+    let packed = execute
+        .call(&mut store, (ptr, len))
+        .map_err(|e| e.to_string())? as u64;
+    let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    if out_len > cfg.max_output {
+        return Err(format!("Output is longer than {} bytes", cfg.max_output));
+    }
+
+    let mut output = vec![0; out_len];
+    memory
+        .read(&store, out_ptr, &mut output)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_slice(&output).map_err(|e| format!("Invalid output: {e}"))
+}
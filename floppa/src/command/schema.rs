@@ -0,0 +1,160 @@
+//! A small subset of json schema, enough to check the data commands are added with
+//!
+//! Supported keywords are `type`, `enum`, `properties`, `required`, `additionalProperties`,
+//! `items`, `minimum`, `maximum`, `minLength`, `maxLength`, `minItems` and `maxItems`.
+//! Anything else is ignored.
+
+use serde_json::Value;
+
+/// Checks `value` against `schema`, returning a description of the first problem found
+pub fn validate(schema: &Value, value: &Value) -> Result<(), String> {
+    validate_at(schema, value, "data")
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        // `true` or an empty schema allows anything
+        return match schema {
+            Value::Bool(false) => Err(format!("`{path}` is not allowed")),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(ty) = schema.get("type") {
+        let matches = match ty {
+            Value::String(ty) => is_type(ty, value),
+            Value::Array(types) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|x| is_type(x, value)),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!(
+                "`{path}` should be {}, but is {}",
+                type_name(ty),
+                value_type(value)
+            ));
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let options = options
+                .iter()
+                .map(|x| format!("`{x}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(format!("`{path}` should be one of {options}"));
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(key) {
+                        return Err(format!("`{path}` is missing `{key}`"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in map {
+                let item_path = format!("{path}.{key}");
+                match properties.and_then(|x| x.get(key)) {
+                    Some(item_schema) => validate_at(item_schema, item, &item_path)?,
+                    None => {
+                        if let Some(extra) = schema.get("additionalProperties") {
+                            validate_at(extra, item, &item_path)?;
+                        }
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            check_bounds(schema, "minItems", "maxItems", items.len(), path, "items")?;
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{path}[{i}]"))?;
+                }
+            }
+        }
+        Value::String(text) => {
+            let len = text.chars().count();
+            check_bounds(schema, "minLength", "maxLength", len, path, "characters")?;
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    return Err(format!("`{path}` should be at least {min}"));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    return Err(format!("`{path}` should be at most {max}"));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn check_bounds(
+    schema: &serde_json::Map<String, Value>,
+    min_key: &str,
+    max_key: &str,
+    len: usize,
+    path: &str,
+    unit: &str,
+) -> Result<(), String> {
+    if let Some(min) = schema.get(min_key).and_then(Value::as_u64) {
+        if (len as u64) < min {
+            return Err(format!("`{path}` should have at least {min} {unit}"));
+        }
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_u64) {
+        if len as u64 > max {
+            return Err(format!("`{path}` should have at most {max} {unit}"));
+        }
+    }
+    Ok(())
+}
+
+fn is_type(ty: &str, value: &Value) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(ty: &Value) -> String {
+    match ty {
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        Value::String(ty) => ty.clone(),
+        _ => ty.to_string(),
+    }
+}
+
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
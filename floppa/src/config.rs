@@ -11,10 +11,33 @@ pub struct Config {
     pub msg_cache: usize,
     /// See [`LoggingConfig`]
     pub logging: LoggingConfig,
-    /// See [`EmojiConfig`]
-    pub emoji: EmojiConfig,
-    /// How many secons between each cycle of the save function
+    /// See [`EmojiConfig`], this is moved into the responders table on start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<EmojiConfig>,
+    /// How many seconds between retrying any changes that failed to save
     pub save_duration: u64,
+    /// How many seconds to wait for running commands and the client when stopping
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout: u64,
+    /// See [`AttachmentConfig`]
+    #[serde(default)]
+    pub attachments: AttachmentConfig,
+    /// See [`ScriptConfig`]
+    #[serde(default)]
+    pub scripting: ScriptConfig,
+    /// See [`PluginConfig`]
+    #[serde(default)]
+    pub plugins: PluginConfig,
+    /// See [`HttpConfig`]
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// See [`GithubConfig`]
+    #[serde(default)]
+    pub github: GithubConfig,
+}
+
+const fn default_shutdown_timeout() -> u64 {
+    10
 }
 
 impl Config {
@@ -48,9 +71,142 @@ pub struct LoggingConfig {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 /// config for reacting to messages that contain a phrase
+///
+/// This has been replaced by auto-responders, and is only read to move it into them
 pub struct EmojiConfig {
     /// The textual representation of the emoji to react with
     pub emoji: String,
     /// What activates the reaction
     pub phrase: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// Limits for files stored by commands
+pub struct AttachmentConfig {
+    /// The max size of a single file, in bytes
+    pub max_size: u32,
+    /// The max amount of files a command can have
+    pub max_count: usize,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            // Discord's upload limit for bots without boosts
+            max_size: 8 * 1024 * 1024,
+            max_count: 10,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// Limits for scripted commands, so they can't stall the bot
+pub struct ScriptConfig {
+    /// How long a script can run for, in milliseconds
+    pub timeout_ms: u64,
+    /// The max amount of operations a script can do
+    pub max_operations: u64,
+    /// The max length of any string a script makes
+    pub max_string_size: usize,
+    /// The max amount of items in any array or map a script makes
+    pub max_collection_size: usize,
+    /// The max length of a script's output
+    pub max_output: usize,
+}
+
+impl Default for ScriptConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 500,
+            max_operations: 1_000_000,
+            max_string_size: 10_000,
+            max_collection_size: 1_000,
+            max_output: 4_000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// Limits for WASM plugin commands, so they can't stall the bot
+pub struct PluginConfig {
+    /// The amount of fuel a plugin gets each run, roughly one per instruction
+    pub fuel: u64,
+    /// The max size of a plugin's memory, in bytes
+    pub max_memory: usize,
+    /// The max length of a plugin's output, in bytes
+    pub max_output: usize,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            max_memory: 16 * 1024 * 1024,
+            max_output: 16_000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// Config for the client commands use to make web requests
+pub struct HttpConfig {
+    /// How long a request can take, in seconds
+    pub timeout: u64,
+    /// The user agent sent with requests
+    pub user_agent: String,
+    /// The max amount of responses to cache
+    pub cache_size: usize,
+    /// How long responses are cached for, in seconds, 0 turns off the cache
+    pub cache_ttl: u64,
+    /// The largest response body that will be read, in bytes
+    pub max_body: usize,
+    /// Sends every request to this url instead, keeping the path and query, for testing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// The hosts api commands, custom wikis and redirects can reach, subdomains are allowed too
+    pub api_hosts: Vec<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: 10,
+            user_agent: concat!("floppa/", env!("CARGO_PKG_VERSION")).to_string(),
+            cache_size: 256,
+            cache_ttl: 5 * 60,
+            max_body: 2 * 1024 * 1024,
+            base_url: None,
+            api_hosts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// Config for expanding GitHub issue references, the repo is set per guild
+pub struct GithubConfig {
+    /// The base url of the GitHub REST API
+    pub api_url: String,
+    /// The most references expanded in one message
+    pub max_links: usize,
+    /// A folder in the run dir to read responses from instead of GitHub, for testing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixtures: Option<String>,
+    /// Saves responses from GitHub into [`Self::fixtures`] instead of reading them
+    pub record: bool,
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "https://api.github.com".to_string(),
+            max_links: 3,
+            fixtures: None,
+            record: false,
+        }
+    }
+}
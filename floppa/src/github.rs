@@ -0,0 +1,209 @@
+use std::{collections::HashSet, sync::LazyLock};
+
+use regex::Regex;
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use serenity::all::{CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter};
+use tracing::info;
+
+use crate::{config::GithubConfig, http::HttpClient, stuff::truncate, Cli, FlopResult};
+
+/// Matches `#1234` and `owner/repo#1234`, with the character before it so it isnt in a word
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^\w/<#&])(?:([A-Za-z0-9][A-Za-z0-9-]*)/([A-Za-z0-9._-]+))?#([0-9]{1,7})\b")
+        .expect("the issue reference regex is valid")
+});
+/// Code and links, which references in are ignored
+static IGNORED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)```.*?```|`[^`]*`|<?https?://\S+").expect("the ignored text regex is valid")
+});
+/// The max length of the issue body shown
+const BODY_LIMIT: usize = 300;
+/// The max length of the issue title shown, leaving room for the number in Discord's 256
+const TITLE_LIMIT: usize = 200;
+
+/// A reference to an issue or pull request, like `GregTechCEu/GregTech#1234`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IssueRef {
+    pub owner: String,
+    pub repo: String,
+    pub number: u32,
+}
+
+/// The parts of an issue from the GitHub REST API that are shown
+#[derive(Debug, Deserialize)]
+pub struct Issue {
+    pub title: String,
+    pub html_url: String,
+    /// Either `open` or `closed`
+    pub state: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    pub user: GithubUser,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub draft: Option<bool>,
+    /// Only set for pull requests
+    #[serde(default)]
+    pub pull_request: Option<PullRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubUser {
+    pub login: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequest {
+    #[serde(default)]
+    pub merged_at: Option<String>,
+}
+
+/// Checks a repo is written like `owner/name`
+pub fn valid_repo(repo: &str) -> bool {
+    let Some((owner, name)) = repo.split_once('/') else {
+        return false;
+    };
+    let valid = |x: &str| {
+        !x.is_empty()
+            && x != "."
+            && x != ".."
+            && x.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    };
+    valid(owner) && valid(name)
+}
+
+/// Finds up to `limit` issue references in a message, `#1234` being in `default_repo`
+pub fn find_refs(text: &str, default_repo: &str, limit: usize) -> Vec<IssueRef> {
+    let text = IGNORED.replace_all(text, " ");
+    let (default_owner, default_name) = default_repo.split_once('/').unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    REFERENCE
+        .captures_iter(&text)
+        .filter_map(|x| {
+            let (owner, repo) = match (x.get(1), x.get(2)) {
+                (Some(owner), Some(repo)) => (owner.as_str(), repo.as_str()),
+                _ => (default_owner, default_name),
+            };
+            let number = x[3].parse().ok().filter(|x| *x > 0)?;
+            valid_repo(&format!("{owner}/{repo}")).then(|| IssueRef {
+                owner: owner.to_owned(),
+                repo: repo.to_owned(),
+                number,
+            })
+        })
+        .filter(|x| seen.insert(x.clone()))
+        .take(limit)
+        .collect()
+}
+
+/// Gets an issue or pull request, [`None`] if it doesn't exist
+///
+/// With fixtures set they are read from `fixtures/owner/repo/number.json` in the run dir
+/// instead, unless recording, where responses from GitHub are saved there
+pub async fn fetch_issue(
+    http: &HttpClient,
+    cfg: &GithubConfig,
+    cli: &Cli,
+    issue: &IssueRef,
+) -> FlopResult<Option<Issue>> {
+    let fixture = cfg.fixtures.as_ref().map(|dir| {
+        cli.get_path(dir)
+            .join(&issue.owner)
+            .join(&issue.repo)
+            .join(format!("{}.json", issue.number))
+    });
+    if let (Some(path), false) = (&fixture, cfg.record) {
+        return match tokio::fs::read(path).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        };
+    }
+
+    let mut url = Url::parse(&cfg.api_url)?;
+    url.path_segments_mut()
+        .map_err(|_| color_eyre::eyre::eyre!("`{}` can't be a base url", cfg.api_url))?
+        .pop_if_empty()
+        .extend(["repos", &issue.owner, &issue.repo, "issues"])
+        .push(&issue.number.to_string());
+    let response = http.get(url).await?;
+    if response.status == StatusCode::NOT_FOUND || response.status == StatusCode::GONE {
+        return Ok(None);
+    }
+    if !response.status.is_success() {
+        color_eyre::eyre::bail!("GitHub responded with {}", response.status);
+    }
+    let found = serde_json::from_slice(&response.body)?;
+
+    if let Some(path) = fixture {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, &response.body).await?;
+        info!("Recorded `{}`", path.display());
+    }
+    Ok(Some(found))
+}
+
+/// Makes an embed showing an issue or pull request
+pub fn issue_embed(issue: &Issue, reference: &IssueRef) -> CreateEmbed {
+    let (kind, state, colour) = match &issue.pull_request {
+        Some(pr) if pr.merged_at.is_some() => ("Pull request", "Merged", 0x8250df),
+        Some(_) if issue.state == "closed" => ("Pull request", "Closed", 0xcf222e),
+        Some(_) if issue.draft == Some(true) => ("Pull request", "Draft", 0x6e7781),
+        Some(_) => ("Pull request", "Open", 0x1f883d),
+        None if issue.state == "closed" => ("Issue", "Closed", 0x8250df),
+        None => ("Issue", "Open", 0x1f883d),
+    };
+
+    let mut author = CreateEmbedAuthor::new(&issue.user.login).url(&issue.user.html_url);
+    if let Some(avatar) = &issue.user.avatar_url {
+        author = author.icon_url(avatar);
+    }
+    let labels = match issue.labels.len() {
+        0 => "None".to_string(),
+        _ => issue
+            .labels
+            .iter()
+            .map(|x| format!("`{}`", x.name))
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+
+    let mut embed = CreateEmbed::new()
+        .title(format!(
+            "#{} {}",
+            reference.number,
+            truncate(&issue.title, TITLE_LIMIT)
+        ))
+        .url(&issue.html_url)
+        .colour(colour)
+        .author(author)
+        .field("State", state, true)
+        .field("Labels", labels, true)
+        .footer(CreateEmbedFooter::new(format!(
+            "{kind} in {}/{}",
+            reference.owner, reference.repo
+        )));
+    if let Some(body) = issue
+        .body
+        .as_deref()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+    {
+        embed = embed.description(truncate(body, BODY_LIMIT));
+    }
+    embed
+}
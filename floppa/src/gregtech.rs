@@ -0,0 +1,282 @@
+//! GregTech CEu voltage tiers and overclocking, and game data dumped to a JSON file in the run dir
+//!
+//! The file looks like this, with everything but names, `type`, `eut` and `duration`
+//! being optional:
+//! ```json
+//! {
+//!     "materials": [{
+//!         "name": "Stainless Steel",
+//!         "formula": "Fe6CrMnNi",
+//!         "components": [{ "name": "Iron", "amount": 6 }, { "name": "Chromium" }],
+//!         "tier": "HV",
+//!         "blast_temperature": 1700,
+//!         "color": 13158640,
+//!         "flags": ["ingot", "plate"]
+//!     }],
+//!     "recipes": [{
+//!         "type": "mixer",
+//!         "inputs": [{ "name": "Iron Dust", "amount": 6 }],
+//!         "outputs": [{ "name": "Stainless Steel Dust", "amount": 9, "chance": 10000 }],
+//!         "fluid_inputs": [{ "name": "Oxygen", "amount": 1000 }],
+//!         "fluid_outputs": [],
+//!         "eut": 120,
+//!         "duration": 900
+//!     }]
+//! }
+//! ```
+
+use std::{collections::BTreeSet, path::Path};
+
+use serde::Deserialize;
+
+/// The voltage tiers, with the max EU/t of one amp at each
+pub const TIERS: [(&str, u64); 15] = [
+    ("ULV", 8),
+    ("LV", 32),
+    ("MV", 128),
+    ("HV", 512),
+    ("EV", 2048),
+    ("IV", 8192),
+    ("LuV", 32768),
+    ("ZPM", 131072),
+    ("UV", 524288),
+    ("UHV", 2097152),
+    ("UEV", 8388608),
+    ("UIV", 33554432),
+    ("UXV", 134217728),
+    ("OpV", 536870912),
+    ("MAX", 2147483648),
+];
+
+/// The lowest tier that can run a recipe using `eut`, as an index into [`TIERS`]
+pub fn tier_for(eut: u64) -> usize {
+    TIERS
+        .iter()
+        .position(|(_, voltage)| eut <= *voltage)
+        .unwrap_or(TIERS.len() - 1)
+}
+
+/// Finds a tier by its name, ignoring case
+pub fn tier_by_name(name: &str) -> Option<usize> {
+    TIERS
+        .iter()
+        .position(|(tier, _)| tier.eq_ignore_ascii_case(name))
+}
+
+/// A recipe after overclocking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overclock {
+    /// How many times it was overclocked
+    pub overclocks: usize,
+    pub eut: u64,
+    /// How long it takes, in ticks
+    pub duration: u64,
+}
+
+/// Overclocks a recipe as far as a machine of `tier` can, each overclock multiplies the EU/t
+/// by 4 and divides the duration by 2, or by 4 if it is `perfect`
+///
+/// Overclocking stops before the duration would go under 1 tick
+pub fn overclock(eut: u64, duration: u64, tier: usize, perfect: bool) -> Overclock {
+    let divisor = if perfect { 4 } else { 2 };
+    let mut result = Overclock {
+        overclocks: 0,
+        eut,
+        duration,
+    };
+    for _ in tier_for(eut)..tier {
+        if result.duration / divisor == 0 {
+            break;
+        }
+        result.overclocks += 1;
+        result.eut = result.eut.saturating_mul(4);
+        result.duration /= divisor;
+    }
+    result
+}
+
+/// Materials and recipes dumped from the game, loaded from a JSON file
+#[derive(Debug, Default, Deserialize)]
+pub struct GtData {
+    #[serde(default)]
+    pub materials: Vec<Material>,
+    #[serde(default)]
+    pub recipes: Vec<Recipe>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Material {
+    pub name: String,
+    /// The chemical formula, like `Fe6CrMnNi`
+    #[serde(default)]
+    pub formula: Option<String>,
+    /// The materials it is made of, and how many of each
+    #[serde(default)]
+    pub components: Vec<Stack>,
+    /// The tier needed to make it, like `HV`
+    #[serde(default)]
+    pub tier: Option<String>,
+    /// The temperature needed in an electric blast furnace, in kelvin
+    #[serde(default)]
+    pub blast_temperature: Option<u32>,
+    /// The colour it is shown with, as `0xRRGGBB`
+    #[serde(default)]
+    pub color: Option<u32>,
+    /// The forms it comes in, like `ingot` or `plate`
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Recipe {
+    /// The recipe type, like `mixer` or `electric_blast_furnace`
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub inputs: Vec<Stack>,
+    #[serde(default)]
+    pub outputs: Vec<Stack>,
+    /// Fluid inputs, in mB
+    #[serde(default)]
+    pub fluid_inputs: Vec<Stack>,
+    /// Fluid outputs, in mB
+    #[serde(default)]
+    pub fluid_outputs: Vec<Stack>,
+    /// The EU used each tick
+    pub eut: u64,
+    /// How long the recipe takes, in ticks
+    pub duration: u64,
+}
+
+/// An amount of an item, fluid or material
+#[derive(Debug, Deserialize)]
+pub struct Stack {
+    pub name: String,
+    #[serde(default = "default_amount")]
+    pub amount: u64,
+    /// The chance of getting an output, out of 10000
+    #[serde(default)]
+    pub chance: Option<u32>,
+}
+
+const fn default_amount() -> u64 {
+    1
+}
+
+impl GtData {
+    /// Reads and parses the data file
+    pub async fn load(path: &Path) -> color_eyre::Result<Self> {
+        let data = tokio::fs::read(path).await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Finds materials by name, best matches first
+    pub fn find_materials(&self, query: &str) -> Vec<&Material> {
+        let mut found = self
+            .materials
+            .iter()
+            .filter_map(|x| Some((score(&x.name, query)?, x)))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|(score, x)| (*score, x.name.len()));
+        found.into_iter().map(|(_, x)| x).collect()
+    }
+
+    /// Finds the item or fluid name closest to the query, looking at recipe outputs if
+    /// `outputs`, or inputs otherwise
+    pub fn find_stack_name(&self, query: &str, outputs: bool) -> Option<&str> {
+        let names = self
+            .recipes
+            .iter()
+            .flat_map(|x| {
+                if outputs {
+                    x.outputs.iter().chain(&x.fluid_outputs)
+                } else {
+                    x.inputs.iter().chain(&x.fluid_inputs)
+                }
+            })
+            .map(|x| x.name.as_str())
+            .collect::<BTreeSet<_>>();
+        names
+            .into_iter()
+            .filter_map(|x| Some((score(x, query)?, x)))
+            .min_by_key(|(score, x)| (*score, x.len()))
+            .map(|(_, x)| x)
+    }
+
+    /// Gets every recipe that makes the item or fluid
+    pub fn recipes_making(&self, name: &str) -> Vec<&Recipe> {
+        self.recipes
+            .iter()
+            .filter(|x| {
+                x.outputs
+                    .iter()
+                    .chain(&x.fluid_outputs)
+                    .any(|x| x.name == name)
+            })
+            .collect()
+    }
+
+    /// Gets every recipe that uses the item or fluid
+    pub fn recipes_using(&self, name: &str) -> Vec<&Recipe> {
+        self.recipes
+            .iter()
+            .filter(|x| {
+                x.inputs
+                    .iter()
+                    .chain(&x.fluid_inputs)
+                    .any(|x| x.name == name)
+            })
+            .collect()
+    }
+}
+
+/// Lowercases and removes anything that isn't a letter or number, so `stainless_steel`
+/// matches `Stainless Steel`
+fn simplify(text: &str) -> String {
+    text.chars()
+        .filter(|x| x.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// How well a name matches a search, lower is better and [`None`] is no match
+///
+/// Exact matches come first, then prefixes, then anywhere in the name, then typos
+fn score(name: &str, query: &str) -> Option<usize> {
+    let name = simplify(name);
+    let query = simplify(query);
+    if query.is_empty() {
+        return None;
+    }
+    if name == query {
+        Some(0)
+    } else if name.starts_with(&query) {
+        Some(1)
+    } else if name.contains(&query) {
+        Some(2)
+    } else {
+        // Allow about one typo every 4 letters
+        let distance = edit_distance(&name, &query);
+        (distance <= query.chars().count() / 4).then_some(3 + distance)
+    }
+}
+
+/// The Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == *y {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
@@ -1,31 +1,38 @@
 use std::{
     collections::HashMap,
-    path::Path,
     sync::{
         atomic::{AtomicI32, Ordering},
-        Arc,
+        Arc, OnceLock,
     },
     time::Duration,
 };
 
 use crate::{
-    command::{CmdCtx, FlopMessagable},
-    config::Config,
+    command::{load_plugins, CmdCtx, FlopMessagable, PageCache},
+    config::{Config, EmojiConfig},
+    github::{fetch_issue, find_refs, issue_embed},
+    http::HttpClient,
     log,
-    sql::{CanonicalisedStatus, CmdNode, FlopDB},
+    responder::{normalise, Responder, ResponderAction, Trigger},
+    schedule::{Cron, Reminder, Schedule, ScheduleAction},
+    shutdown::SHUTDOWN,
+    sql::{CanonicalisedStatus, CmdNode, FlopDB, REACTION_COUNTER},
     Cli, FlopError, FlopResult,
 };
-use serenity::{async_trait, http::Http, model::prelude::*, prelude::*};
-use tokio::{
-    fs::File,
-    io::{AsyncReadExt, AsyncWriteExt},
-    time,
+use serenity::{
+    async_trait,
+    builder::{CreateAllowedMentions, CreateMessage},
+    http::Http,
+    model::prelude::*,
+    prelude::*,
 };
-use tracing::{debug, error, info};
+use tokio::{fs, fs::File, io::AsyncReadExt, time};
+use tracing::{debug, error, info, warn};
 
 const FALLBACK_EMOTE: &str = "⚠";
 const RESPONSE_CACHE_SIZE: usize = 512;
-/// The count of emoji reactions this bot has done
+/// The count of emoji reactions this bot has done since the last reset,
+/// kept in the db under [`REACTION_COUNTER`]
 pub static REACTION_COUNT: AtomicI32 = AtomicI32::new(0);
 
 #[derive(Debug)]
@@ -33,15 +40,13 @@ pub static REACTION_COUNT: AtomicI32 = AtomicI32::new(0);
 pub struct FlopHandler {
     cfg: Config,
     cli: Cli,
-    emoji: EmojiCache,
     data: Arc<RwLock<FlopDB>>,
-    response_cache: RwLock<HashMap<MessageId, MessageId>>,
-}
-
-#[derive(Debug)]
-struct EmojiCache {
-    emoji: ReactionType,
-    text: String,
+    response_cache: RwLock<HashMap<MessageId, Vec<MessageId>>>,
+    pages: PageCache,
+    /// The shared client commands make web requests with
+    http: HttpClient,
+    /// The context from when the bot first connected, used to run schedules
+    ready_ctx: OnceLock<Context>,
 }
 
 impl FlopHandler {
@@ -49,18 +54,11 @@ impl FlopHandler {
         self.data.clone()
     }
 
-    pub async fn new(cfg: Config, cli: Cli) -> Self {
+    pub async fn new(mut cfg: Config, cli: Cli) -> Self {
         // TODO: Move the init stuff to a method taking &mut self
-        let emoji = EmojiCache {
-            emoji: cfg.emoji.emoji.as_str().try_into().unwrap_or_else(|e| {
-                error!("Error constructing reaction emoji:```\n{e}```");
-                ReactionType::Unicode(FALLBACK_EMOTE.to_string())
-            }),
-            text: fomat_reaction_string(&cfg.emoji.phrase),
-        };
-
-        if let Err(e) = Self::init_emoji(&cli).await {
-            error!("Error initalising the reaction counter: `{e}`")
+        // Plugins have to be loaded before the commands using them are constructed
+        if let Err(e) = load_plugins(&cli) {
+            error!("Error loading plugins: `{e}`")
         }
 
         let data = Arc::new(RwLock::new(match FlopDB::init(&cli).await {
@@ -68,16 +66,39 @@ impl FlopHandler {
             Err(e) => panic!("Error connstructing database: `{e:?}`"),
         }));
 
+        if let Err(e) = Self::init_emoji(&cli, &*data.read().await).await {
+            error!("Error initalising the reaction counter: `{e}`")
+        }
+
+        if let Some(emoji) = cfg.emoji.take() {
+            match Self::migrate_emoji(&emoji, &mut *data.write().await).await {
+                Ok(()) => {
+                    if let Err(e) = cfg.write_to_fs(&cli).await {
+                        error!("Error removing the emoji config: `{e}`")
+                    }
+                }
+                Err(e) => error!("Error moving the emoji config into a responder: `{e}`"),
+            }
+        }
+
+        let http = match HttpClient::new(&cfg.http) {
+            Ok(i) => i,
+            Err(e) => panic!("Error constructing the http client: `{e:?}`"),
+        };
+
         Self {
             cfg,
             cli,
-            emoji,
             data,
+            http,
             response_cache: RwLock::new(HashMap::with_capacity(RESPONSE_CACHE_SIZE)),
+            pages: PageCache::default(),
+            ready_ctx: OnceLock::new(),
         }
     }
 
-    async fn init_emoji(cli: &Cli) -> FlopResult<()> {
+    /// Loads the reaction count, moving it out of the old `reaction_count` file if there is one
+    async fn init_emoji(cli: &Cli, db: &FlopDB) -> FlopResult<()> {
         let path = cli.get_path("reaction_count");
 
         if !path.is_file() && path.exists() {
@@ -85,22 +106,183 @@ impl FlopHandler {
             return Err(FlopError::InvalidPath(msg).into());
         }
 
-        if !path.exists() {
-            REACTION_COUNT.store(0, Ordering::Relaxed);
-            File::create(path).await?.write_i32(0).await?;
-        } else {
-            let mut count_file = File::open(path).await?;
-            REACTION_COUNT.store(count_file.read_i32().await?, Ordering::Relaxed);
+        if path.exists() {
+            let count = File::open(&path).await?.read_i32().await?;
+            db.init_counter(REACTION_COUNTER, count.into()).await?;
+            fs::remove_file(&path).await?;
+            info!(
+                "Moved the reaction count from `{}` into the db",
+                path.display()
+            );
         }
+
+        let count = db.get_counter(REACTION_COUNTER).await?;
+        REACTION_COUNT.store(count as i32, Ordering::Relaxed);
         Ok(())
     }
 
-    // Returns the message id of the old response to the message, if there is one
-    async fn handle_command(&self, ctx: &Context, msg: Message) -> Option<MessageId> {
+    /// Moves the old single emoji config into an auto-responder
+    async fn migrate_emoji(emoji: &EmojiConfig, db: &mut FlopDB) -> FlopResult<()> {
+        // In case the config couldnt be written last time
+        if db
+            .responders()
+            .iter()
+            .any(|x| x.guild.is_none() && x.trigger.pattern() == emoji.phrase)
+        {
+            return Ok(());
+        }
+        let action = ResponderAction::parse("react", &emoji.emoji).unwrap_or_else(|e| {
+            error!("Error constructing reaction emoji:```\n{e}```");
+            ResponderAction::React(vec![ReactionType::Unicode(FALLBACK_EMOTE.to_string())])
+        });
+        let responder = Responder::new(
+            0,
+            UserId::default(),
+            None,
+            None,
+            Trigger::parse("substring", &emoji.phrase)?,
+            action,
+            Duration::ZERO,
+        )
+        .with_counts(true);
+        let id = db.add_responder(responder).await?;
+        info!("Moved the emoji config into responder {id}");
+        Ok(())
+    }
+
+    /// Runs the auto-responders that match a message
+    ///
+    /// Edited messages only get reactions, so they aren't replied to again
+    async fn respond(&self, ctx: &Context, msg: &Message, edited: bool) {
+        let normalised = normalise(&msg.content);
+        let matching = self
+            .data
+            .read()
+            .await
+            .responders()
+            .iter()
+            .filter(|x| x.in_scope(msg) && x.trigger.matches(&msg.content, &normalised))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for responder in matching {
+            let react = matches!(responder.action, ResponderAction::React(_));
+            // Bots arent replied to, so they cant set each other off
+            if !react && (edited || msg.author.bot) {
+                continue;
+            }
+            if !responder.try_fire(msg.channel_id) {
+                continue;
+            }
+
+            let result = match &responder.action {
+                ResponderAction::React(emoji) => {
+                    self.react(ctx, msg, emoji, responder.counts).await;
+                    continue;
+                }
+                ResponderAction::Reply(text) => FlopMessagable::Text(text.clone()),
+                ResponderAction::Command(command) => {
+                    let Some(_running) = SHUTDOWN.start_command() else {
+                        return;
+                    };
+                    // Ran as the sender, so they still need permission for what it does
+                    let mut cmd_msg = msg.clone();
+                    cmd_msg.content = format!("{}{command}", self.cfg.prefix);
+                    match self.run_command(ctx, &cmd_msg).await {
+                        Some(result) => result,
+                        None => continue,
+                    }
+                }
+            };
+            if let Err(e) = self
+                .send_tracked(result, msg.channel_id, Some(msg), msg.author.id, &ctx.http)
+                .await
+            {
+                error!(
+                    "Error sending responder {} @ `{}`:```rust\n{e}```",
+                    responder.id,
+                    msg.link()
+                )
+            }
+        }
+    }
+
+    /// Replies with embeds for the GitHub issues and pull requests a message references
+    async fn expand_issues(&self, ctx: &Context, msg: &Message) {
+        let Some(guild) = msg.guild_id else {
+            return;
+        };
+        if msg.author.bot || msg.content.starts_with(&self.cfg.prefix) {
+            return;
+        }
+        let Some(repo) = self.data.read().await.github_repo(guild).map(str::to_owned) else {
+            return;
+        };
+        let Some(_running) = SHUTDOWN.start_command() else {
+            return;
+        };
+
+        let mut embeds = Vec::new();
+        for reference in find_refs(&msg.content, &repo, self.cfg.github.max_links) {
+            match fetch_issue(&self.http, &self.cfg.github, &self.cli, &reference).await {
+                Ok(Some(issue)) => embeds.push(issue_embed(&issue, &reference)),
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "Error getting {}/{}#{}: `{e}`",
+                    reference.owner, reference.repo, reference.number
+                ),
+            }
+        }
+        if embeds.is_empty() {
+            return;
+        }
+        if let Err(e) = FlopMessagable::Embeds(embeds).send(msg, &ctx.http).await {
+            error!("Error expanding issues in `{}`: `{e}`", msg.link())
+        }
+    }
+
+    /// Reacts to a message with each emoji, adding the message to the flop counters if `counts`
+    async fn react(&self, ctx: &Context, msg: &Message, emoji: &[ReactionType], counts: bool) {
+        for emoji in emoji {
+            if let Err(e) = msg.react(&ctx.http, emoji.clone()).await {
+                if e.to_string() != "Missing Permissions" {
+                    error!("Error reacting to `{}`:`{e:?}`", msg.link())
+                }
+                return;
+            }
+        }
+        if !counts {
+            return;
+        }
+
+        // Edits react again, but the message is only counted the first time
+        match self
+            .data
+            .read()
+            .await
+            .add_reaction(msg.guild_id, msg.channel_id, msg.id, msg.author.id)
+            .await
+        {
+            Ok(Some(count)) => REACTION_COUNT.store(count as i32, Ordering::Relaxed),
+            Ok(None) => {}
+            Err(e) => error!("Error saving the reaction count: `{e}`"),
+        }
+    }
+
+    // Returns the message ids of the old response to the message, if there is one
+    async fn handle_command(&self, ctx: &Context, msg: Message) -> Option<Vec<MessageId>> {
         // Check if the messages starts with prefix and the user isnt a bot
         if msg.author.bot || !msg.content.starts_with(&self.cfg.prefix) {
             return None;
         }
+        // Dont start new commands while shutting down
+        let _running = SHUTDOWN.start_command()?;
+        let result = self.run_command(ctx, &msg).await?;
+        self.process_messageable(&msg, result, &ctx.http).await
+    }
+
+    /// Finds and runs the command the message calls, returning what should be sent back
+    async fn run_command(&self, ctx: &Context, msg: &Message) -> Option<FlopMessagable> {
         // Get the name of the command to be ran
         let name = &msg.content[self.cfg.prefix.len()..];
         debug!("command {name} was called");
@@ -117,28 +299,16 @@ impl FlopHandler {
         match canonicalised.status {
             CanonicalisedStatus::Success => (),
             CanonicalisedStatus::Overflow => {
-                return self
-                    .process_messageable(
-                        &msg,
-                        FlopMessagable::Text(
-                            "This command is nested too deep to be run".to_string(),
-                        ),
-                        &ctx.http,
-                    )
-                    .await
+                return Some(FlopMessagable::Text(
+                    "This command is nested too deep to be run".to_string(),
+                ))
             }
             CanonicalisedStatus::NotFound => {
                 if canonicalised.stack.len() > 1 {
                     if let Some((registry, name)) = canonicalised.stack.last() {
-                        return self
-                            .process_messageable(
-                                &msg,
-                                FlopMessagable::Text(format!(
-                                    "Cannot find command {registry}:{name}"
-                                )),
-                                &ctx.http,
-                            )
-                            .await;
+                        return Some(FlopMessagable::Text(format!(
+                            "Cannot find command {registry}:{name}"
+                        )));
                     }
                 } else {
                     return None;
@@ -155,26 +325,14 @@ impl FlopHandler {
                         l.push_str(" -> ");
                         l
                     });
-                return self
-                    .process_messageable(
-                        &msg,
-                        FlopMessagable::Text(format!("Recursive loop:\n{chain}")),
-                        &ctx.http,
-                    )
-                    .await;
+                return Some(FlopMessagable::Text(format!("Recursive loop:\n{chain}")));
             }
             CanonicalisedStatus::FailedSubcommand => {
-                return self
-                    .process_messageable(
-                        &msg,
-                        FlopMessagable::Text(format!(
-                            "{0}{1} is a registry, usage `{0}{1} [command name]`",
-                            self.cfg.prefix.len(),
-                            canonicalised.call
-                        )),
-                        &ctx.http,
-                    )
-                    .await
+                return Some(FlopMessagable::Text(format!(
+                    "{0}{1} is a registry, usage `{0}{1} [command name]`",
+                    self.cfg.prefix.len(),
+                    canonicalised.call
+                )))
             }
         }
 
@@ -188,41 +346,93 @@ impl FlopHandler {
             error!("Somehow got no response from a canonicalisaion");
             return None;
         };
-        let mut entry = entry.lock().await;
         drop(data_lock);
 
+        // Only hold the entry while getting the command out of it,
+        // so the command can run alongside other uses of it
+        let mut entry_lock = entry.lock().await;
+        let owner = *entry_lock.get_owner();
+        let added = entry_lock.get_added();
+        let cmd = match entry_lock.get_node() {
+            CmdNode::Cmd(cmd) => cmd.clone(),
+            CmdNode::Broken { error, .. } => {
+                let text =
+                    format!("⚠️ `{name}` failed to load, ask an admin to fix it:```{error}```");
+                drop(entry_lock);
+                return Some(FlopMessagable::Text(text));
+            }
+            node => {
+                error!("Expected a command, not a `{node:?}`!");
+                return None;
+            }
+        };
+        drop(entry_lock);
+
         // Execute the command
         let _typing = ctx.http.start_typing(msg.channel_id);
         let cmd_ctx = CmdCtx {
             ctx,
-            command: &(self.cfg.prefix.clone() + &canonicalised.call),
+            command: &(self.cfg.prefix.clone() + canonicalised.call.as_str()),
             registry: &registry,
             name: &name,
-            owner: *entry.get_owner(),
-            added: entry.get_added(),
+            owner,
+            added,
+            cfg: &self.cfg,
+            http: &self.http,
         };
-        let node = entry.get_node();
-
-        let CmdNode::Cmd(cmd) = node else {
-            error!("Expected a command, not a `{node:?}`!");
-            return None;
+        // Only commands whose data changed are written in full
+        let before = cmd.save();
+        let result = cmd.execute(msg, cmd_ctx, &self.data).await;
+        let changed = cmd.save() != before;
+
+        // The entry is unlocked before the db is, as removing a command locks them the other way
+        let (id, uses, last_used) = {
+            let mut entry_lock = entry.lock().await;
+            entry_lock.add_use();
+            (
+                entry_lock.get_id(),
+                entry_lock.get_uses(),
+                entry_lock.get_last_used(),
+            )
         };
-
-        let result = cmd.execute(&msg, cmd_ctx, &self.data).await;
-        // Drop the lock
-        drop(entry);
-        // Send the result
+        let data = self.data.read().await;
+        if let Err(e) = data.record_use(id, uses, last_used).await {
+            error!("Error saving the uses of {name}: `{e}`");
+        }
+        drop(data);
+        if changed {
+            self.data.write().await.mark_dirty(registry, name.clone());
+        }
+        // Write anything the command changed straight away
+        FlopDB::flush(&self.data).await;
         match result {
-            Ok(m) => {
-                if !m.is_none() {
-                    return self.process_messageable(&msg, m, &ctx.http).await;
-                }
-            }
+            Ok(m) if m.is_none() => None,
+            Ok(m) => Some(m),
             Err(e) => {
-                error!("Error running ${name} @ `{}`:```rust\n{e}```", msg.link())
+                error!("Error running ${name} @ `{}`:```rust\n{e}```", msg.link());
+                None
             }
         }
-        None
+    }
+
+    /// Sends a message, keeping any pages so their buttons can flip through them for `user`
+    async fn send_tracked(
+        &self,
+        messagable: FlopMessagable,
+        channel: ChannelId,
+        reply: Option<&Message>,
+        user: UserId,
+        http: &Http,
+    ) -> FlopResult<Vec<Message>> {
+        let pages = match &messagable {
+            FlopMessagable::Pages(pages) if !pages.is_empty() => Some(pages.clone()),
+            _ => None,
+        };
+        let sent = messagable.send_to(channel, reply, http).await?;
+        if let (Some(pages), Some(first)) = (pages, sent.first()) {
+            self.pages.insert(first.id, user, pages).await;
+        }
+        Ok(sent)
     }
 
     /// Adds a replyed message to the cache
@@ -231,13 +441,21 @@ impl FlopHandler {
         source: &Message,
         messagble: FlopMessagable,
         http: &Http,
-    ) -> Option<MessageId> {
-        let result = messagble.send(source, http).await;
+    ) -> Option<Vec<MessageId>> {
+        let result = self
+            .send_tracked(
+                messagble,
+                source.channel_id,
+                Some(source),
+                source.author.id,
+                http,
+            )
+            .await;
         if let Err(e) = result {
             error!("Error sending reply @ `{}`:```rust\n{e}```", source.link())
-        } else if let Ok(reply) = result {
+        } else if let Ok(replies) = result {
             let mut lock = self.response_cache.write().await;
-            let old = lock.insert(source.id, reply.id);
+            let old = lock.insert(source.id, replies.into_iter().map(|x| x.id).collect());
             if lock.len() >= RESPONSE_CACHE_SIZE {
                 let mut keys = lock.keys().cloned().collect::<Vec<_>>();
                 keys.sort();
@@ -251,21 +469,184 @@ impl FlopHandler {
         }
         None
     }
+
+    /// Runs all the schedules that are due, moving repeating ones to their next run
+    pub async fn run_due_schedules(&self) {
+        // Schedules cant be sent until the bot has connected
+        let Some(ctx) = self.ready_ctx.get() else {
+            return;
+        };
+        let now = Timestamp::now().unix_timestamp();
+        let due = match self.data.read().await.due_schedules(now).await {
+            Ok(due) => due,
+            Err(e) => {
+                error!("Error getting due schedules: `{e}`");
+                return;
+            }
+        };
+
+        for schedule in due {
+            // Dont start new schedules while shutting down, they will run on the next start
+            let Some(_running) = SHUTDOWN.start_command() else {
+                return;
+            };
+            // Move the schedule on before running it, so it cant run twice if something goes wrong
+            if let Err(e) = self.advance_schedule(&schedule, now).await {
+                error!("Error updating schedule {}: `{e}`", schedule.id);
+                continue;
+            }
+            self.run_schedule(ctx, &schedule).await;
+        }
+    }
+
+    /// Sends all the reminders that are due, including ones missed while offline
+    pub async fn send_due_reminders(&self) {
+        let Some(ctx) = self.ready_ctx.get() else {
+            return;
+        };
+        let now = Timestamp::now().unix_timestamp();
+        let due = match self.data.read().await.due_reminders(now).await {
+            Ok(due) => due,
+            Err(e) => {
+                error!("Error getting due reminders: `{e}`");
+                return;
+            }
+        };
+
+        for reminder in due {
+            let Some(_running) = SHUTDOWN.start_command() else {
+                return;
+            };
+            // Removed before sending, so a reminder that cant be sent isnt retried forever
+            match self
+                .data
+                .read()
+                .await
+                .delete_reminder(reminder.id, None)
+                .await
+            {
+                Ok(true) => self.send_reminder(ctx, &reminder, now).await,
+                // Cancelled since it was fetched
+                Ok(false) => {}
+                Err(e) => error!("Error removing reminder {}: `{e}`", reminder.id),
+            }
+        }
+    }
+
+    /// Sends a reminder by DM or as a reply, falling back to the channel it was made in
+    async fn send_reminder(&self, ctx: &Context, reminder: &Reminder, now: i64) {
+        let mut text = format!(
+            "⏰ {}, <t:{}:R> you asked to be reminded:\n{}",
+            reminder.user.mention(),
+            reminder.added,
+            reminder.text
+        );
+        // Give some leeway for the scheduler tick
+        if now - reminder.due > 60 {
+            text.push_str(&format!("\n-# This was due <t:{}:R>", reminder.due));
+        }
+        // Only ping the person being reminded, whatever the reminder says
+        let builder = CreateMessage::new()
+            .content(text)
+            .allowed_mentions(CreateAllowedMentions::new().users([reminder.user]));
+
+        if reminder.dm {
+            let sent = match reminder.user.create_dm_channel(&ctx.http).await {
+                Ok(dm) => dm
+                    .send_message(&ctx.http, builder.clone())
+                    .await
+                    .map(|_| ()),
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(()) => return,
+                Err(e) => debug!("Error DMing reminder {}: `{e}`", reminder.id),
+            }
+        } else {
+            let reply = builder
+                .clone()
+                .reference_message((reminder.channel, reminder.message));
+            match reminder.channel.send_message(&ctx.http, reply).await {
+                Ok(_) => return,
+                // Most likely the message was deleted
+                Err(e) => debug!("Error replying with reminder {}: `{e}`", reminder.id),
+            }
+        }
+
+        if let Err(e) = reminder.channel.send_message(&ctx.http, builder).await {
+            error!(
+                "Error sending reminder {} in {}:```rust\n{e}```",
+                reminder.id,
+                reminder.channel.mention()
+            )
+        }
+    }
+
+    /// Sets when a schedule runs next, or removes it if it doesnt repeat
+    async fn advance_schedule(&self, schedule: &Schedule, now: i64) -> FlopResult<()> {
+        let db = self.data.read().await;
+        let Some(cron) = &schedule.cron else {
+            db.delete_schedule(schedule.id).await?;
+            return Ok(());
+        };
+        // Runs missed while offline are skipped, it only runs once to catch up
+        match Cron::parse(cron).map(|x| x.next_after(now)) {
+            Ok(Some(next)) => db.update_schedule(schedule.id, next, false).await?,
+            Ok(None) => {
+                warn!("Schedule {} never runs again, pausing it", schedule.id);
+                db.update_schedule(schedule.id, schedule.next_run, true)
+                    .await?
+            }
+            Err(e) => {
+                warn!(
+                    "Schedule {} has an invalid cron `{e}`, pausing it",
+                    schedule.id
+                );
+                db.update_schedule(schedule.id, schedule.next_run, true)
+                    .await?
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends a schedule's message, or runs its command as the schedule's owner
+    async fn run_schedule(&self, ctx: &Context, schedule: &Schedule) {
+        debug!("Running schedule {}", schedule.id);
+        let result = match &schedule.action {
+            ScheduleAction::Message(text) => FlopMessagable::Text(text.clone()),
+            ScheduleAction::Command(command) => {
+                // Commands read everything off the message, so pretend the owner sent one
+                let mut msg = Message::default();
+                msg.content = format!("{}{command}", self.cfg.prefix);
+                msg.channel_id = schedule.channel;
+                msg.guild_id = schedule.guild;
+                msg.author.id = schedule.owner;
+                msg.timestamp = Timestamp::now();
+                match self.run_command(ctx, &msg).await {
+                    Some(result) => result,
+                    None => return,
+                }
+            }
+        };
+
+        if let Err(e) = self
+            .send_tracked(result, schedule.channel, None, schedule.owner, &ctx.http)
+            .await
+        {
+            error!(
+                "Error sending schedule {} in {}:```rust\n{e}```",
+                schedule.id,
+                schedule.channel.mention()
+            )
+        }
+    }
 }
 
 #[async_trait]
 impl EventHandler for FlopHandler {
     async fn message(&self, ctx: Context, msg: Message) {
-        if fomat_reaction_string(&msg.content).contains(&self.emoji.text) {
-            let result = msg.react(&ctx.http, self.emoji.emoji.clone()).await;
-            if let Err(e) = result {
-                if e.to_string() != "Missing Permissions" {
-                    error!("Error reacting to `{}`:`{e:?}`", msg.link())
-                }
-            } else {
-                REACTION_COUNT.fetch_add(1, Ordering::Relaxed);
-            }
-        }
+        self.respond(&ctx, &msg, false).await;
+        self.expand_issues(&ctx, &msg).await;
         // Handle potental command calls
         self.handle_command(&ctx, msg).await;
     }
@@ -295,19 +676,10 @@ impl EventHandler for FlopHandler {
             }
         };
 
-        // Use the normal message handler, just copy pasted (maybe move to fn)
-        if fomat_reaction_string(&msg.content).contains(&self.emoji.text) {
-            let result = msg.react(&ctx.http, self.emoji.emoji.clone()).await;
-            if let Err(e) = result {
-                if e.to_string() != "Missing Permissions" {
-                    error!("Error reacting to `{}`:`{e:?}`", msg.link())
-                }
-            } else {
-                REACTION_COUNT.fetch_add(1, Ordering::Relaxed);
-            }
-        }
+        // Use the normal message handler
+        self.respond(&ctx, &msg, true).await;
         // Handle potental command calls
-        if let Some(id) = self.handle_command(&ctx, msg).await {
+        for id in self.handle_command(&ctx, msg).await.unwrap_or_default() {
             if let Err(e) = event.channel_id.delete_message(&ctx.http, id).await {
                 error!(
                     "Error deleting message {}```rust\n{e}```",
@@ -317,8 +689,10 @@ impl EventHandler for FlopHandler {
         }
     }
 
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         info!("Connected as {}", ready.user.name);
+        // Only the first is kept, as reconnecting doesnt change anything schedules use
+        let _ = self.ready_ctx.set(ctx);
     }
 
     async fn guild_create(&self, _: Context, guild: Guild, _: Option<bool>) {
@@ -350,7 +724,7 @@ impl EventHandler for FlopHandler {
         guild_id: Option<GuildId>,
     ) {
         let lock = self.response_cache.read().await;
-        if let Some(id) = lock.get(&deleted_message_id) {
+        for id in lock.get(&deleted_message_id).into_iter().flatten() {
             if let Err(e) = channel_id.delete_message(&ctx.http, id).await {
                 error!(
                     "Error deleting message {}```rust\n{e}```",
@@ -359,55 +733,21 @@ impl EventHandler for FlopHandler {
             }
         }
     }
-}
 
-/// Dedups and removes whitespace from a string
-fn fomat_reaction_string(text: &str) -> String {
-    let mut chars: Vec<char> = text.chars().filter(|x| !x.is_whitespace()).collect();
-    chars.dedup();
-    chars.into_iter().collect()
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::Component(component) = interaction {
+            self.pages.handle(&ctx, &component).await;
+        }
+    }
 }
 
-/// Function to sync db consistantly
-pub async fn db_sync_loop(duration: u64, data: Arc<RwLock<FlopDB>>, cli: Cli) {
+/// Function to retry syncing anything that failed to be written
+pub async fn db_sync_loop(duration: u64, data: Arc<RwLock<FlopDB>>) {
     let mut interval = time::interval(Duration::from_secs(duration));
-    let emote_path = cli.get_path("reaction_count");
     debug!("Started save loop");
     loop {
         interval.tick().await;
 
-        db_sync(data.clone(), &emote_path).await;
-    }
-}
-
-pub async fn db_sync(data: Arc<RwLock<FlopDB>>, reaction_path: impl AsRef<Path>) {
-    // Get and drain the dirty commands
-    let mut lock = data.write().await;
-    let dirty = lock.drain_dirty();
-    let removed = lock.drain_removed();
-    let roles = lock.drain_roles();
-    // Drop lock to free db to be used for other purposes
-    drop(lock);
-
-    // Get a read lock, as we dont need to write any data for this potentally long running function
-    let lock = data.read().await;
-    if let Err(e) = lock.sync(dirty, removed, roles).await {
-        error!("Error syncing to disk```rust\n{e}```");
-    }
-
-    // Sync REACTION_COUNT
-    let mut reaction_file = match File::create(reaction_path).await {
-        Err(e) => {
-            error!("Error opening file to sync reaction counts: `{e}`");
-            return;
-        }
-        Ok(f) => f,
-    };
-
-    if let Err(e) = reaction_file
-        .write_i32(REACTION_COUNT.load(Ordering::Relaxed))
-        .await
-    {
-        error!("error writing reaction count to disk: `{e}`")
+        FlopDB::flush(&data).await;
     }
 }
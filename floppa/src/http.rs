@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use reqwest::{redirect::Policy, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+use crate::{config::HttpConfig, FlopResult};
+
+/// The most redirects followed for one request
+const MAX_REDIRECTS: usize = 10;
+
+/// The shared client commands use to make outbound requests, with a timeout and a cache
+#[derive(Debug)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    /// Requests are sent here instead, keeping their path and query, if set
+    base_url: Option<Url>,
+    /// The largest body that will be read, in bytes
+    max_body: usize,
+    cache: Mutex<ResponseCache>,
+}
+
+/// A response that was fetched, or taken from the cache
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+/// Successful responses by url, dropping the least recently used when full
+#[derive(Debug)]
+struct ResponseCache {
+    entries: HashMap<String, CacheEntry>,
+    size: usize,
+    ttl: Duration,
+    /// Goes up with every lookup, to find the least recently used entry
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    response: Arc<HttpResponse>,
+    fetched: Instant,
+    last_used: u64,
+}
+
+impl HttpClient {
+    pub fn new(cfg: &HttpConfig) -> FlopResult<Self> {
+        // Redirects could go anywhere, so only follow ones that stay on the first host or
+        // go to an allowed host
+        let hosts = cfg.api_hosts.clone();
+        let policy = Policy::custom(move |attempt| {
+            let first = attempt.previous().first().and_then(Url::host_str);
+            let host = attempt.url().host_str().unwrap_or_default();
+            if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if first == Some(host) || host_allowed(&hosts, host) {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        });
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(cfg.timeout))
+            .user_agent(&cfg.user_agent)
+            .redirect(policy)
+            .build()?;
+        let base_url = cfg.base_url.as_deref().map(Url::parse).transpose()?;
+        Ok(Self {
+            client,
+            base_url,
+            max_body: cfg.max_body,
+            cache: Mutex::new(ResponseCache {
+                entries: HashMap::with_capacity(cfg.cache_size),
+                size: cfg.cache_size,
+                ttl: Duration::from_secs(cfg.cache_ttl),
+                clock: 0,
+            }),
+        })
+    }
+
+    /// Makes a GET request, using the cached response if there is a recent one
+    pub async fn get(&self, url: Url) -> FlopResult<Arc<HttpResponse>> {
+        let url = self.rewrite(url);
+        if let Some(response) = self.lock_cache().get(url.as_str()) {
+            debug!("Using the cached response for `{url}`");
+            return Ok(response);
+        }
+
+        let mut response = self.client.get(url.clone()).send().await?;
+        let status = response.status();
+        // Read in chunks, so a huge response is never kept in memory or the cache
+        let too_big = || color_eyre::eyre::eyre!("The response is over {} bytes", self.max_body);
+        if response
+            .content_length()
+            .is_some_and(|x| x > self.max_body as u64)
+        {
+            return Err(too_big());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.max_body {
+                return Err(too_big());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let response = Arc::new(HttpResponse { status, body });
+
+        // Errors might not happen next time, so are never cached
+        if status.is_success() {
+            self.lock_cache().insert(url.into(), response.clone());
+        }
+        Ok(response)
+    }
+
+    /// Makes a GET request and decodes the response as json, erroring on non success statuses
+    pub async fn get_json<T: DeserializeOwned>(&self, url: Url) -> FlopResult<T> {
+        let response = self.get(url).await?;
+        if !response.status.is_success() {
+            color_eyre::eyre::bail!("The server responded with {}", response.status);
+        }
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+
+    /// Points a url at [`Self::base_url`], if it is set
+    fn rewrite(&self, url: Url) -> Url {
+        let Some(base) = &self.base_url else {
+            return url;
+        };
+        let mut new = base.clone();
+        new.set_path(&format!(
+            "{}{}",
+            base.path().trim_end_matches('/'),
+            url.path()
+        ));
+        new.set_query(url.query());
+        new
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, ResponseCache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Checks a host is one of `hosts`, or a subdomain of one
+pub fn host_allowed(hosts: &[String], host: &str) -> bool {
+    hosts
+        .iter()
+        .any(|x| host == x || host.ends_with(&format!(".{x}")))
+}
+
+impl ResponseCache {
+    fn get(&mut self, url: &str) -> Option<Arc<HttpResponse>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(url)?;
+        if entry.fetched.elapsed() > self.ttl {
+            self.entries.remove(url);
+            return None;
+        }
+        entry.last_used = self.clock;
+        Some(entry.response.clone())
+    }
+
+    fn insert(&mut self, url: String, response: Arc<HttpResponse>) {
+        if self.size == 0 || self.ttl.is_zero() {
+            return;
+        }
+        if self.entries.len() >= self.size && !self.entries.contains_key(&url) {
+            // Expired entries go first, then the least recently used
+            let ttl = self.ttl;
+            self.entries.retain(|_, x| x.fetched.elapsed() <= ttl);
+            if self.entries.len() >= self.size {
+                if let Some(oldest) = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, x)| x.last_used)
+                    .map(|(url, _)| url.clone())
+                {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(
+            url,
+            CacheEntry {
+                response,
+                fetched: Instant::now(),
+                last_used: self.clock,
+            },
+        );
+    }
+}
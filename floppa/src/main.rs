@@ -1,7 +1,15 @@
+mod calc;
+mod chem;
 mod command;
 pub mod config;
+mod github;
+mod gregtech;
 mod handler;
+mod http;
 mod log;
+mod responder;
+mod schedule;
+mod shutdown;
 mod sql;
 mod stuff;
 
@@ -9,6 +17,8 @@ use std::{
     fmt::Display,
     path::{Path, PathBuf},
     process,
+    sync::Arc,
+    time::Duration,
 };
 
 use clap::Parser;
@@ -17,7 +27,9 @@ use config::Config;
 use handler::FlopHandler;
 use log::FlopLog;
 use serenity::{cache::Settings as CacheSettings, model::prelude::*, prelude::*};
-use tokio::{fs, join, runtime::Handle};
+use shutdown::{ExitReason, SHUTDOWN};
+use sql::FlopDB;
+use tokio::{fs, time};
 use tracing::{error, warn};
 use tracing_subscriber::prelude::*;
 
@@ -56,52 +68,57 @@ async fn main() {
 
     cache_settings.max_messages = cfg.msg_cache;
 
-    let handler = FlopHandler::new(cfg.clone(), cli.clone()).await;
+    let handler = Arc::new(FlopHandler::new(cfg.clone(), cli.clone()).await);
     let db = handler.get_db();
 
     let mut client = Client::builder(&token, intents)
-        .event_handler(handler)
+        .event_handler_arc(handler.clone())
         .cache_settings(cache_settings)
         .await
         .expect("Error building Client");
 
-    // Spawn task to consistantly sync db
-    let a = tokio::spawn(handler::db_sync_loop(
-        cfg.save_duration,
-        db.clone(),
-        cli.clone(),
-    ));
+    let shard_manager = client.shard_manager.clone();
+
+    // Spawn task to retry syncing the db
+    let sync_task = tokio::spawn(handler::db_sync_loop(cfg.save_duration, db.clone()));
+    // Spawn task to run schedules when they are due
+    let schedule_task = tokio::spawn(schedule::schedule_loop(handler));
 
     // Spawn the main task
-    let moved_db = db.clone();
-    let moved_cli = cli.clone();
-    let b = tokio::spawn(async move {
-        if let Err(e) = client.start().await {
-            error!("Fatal error running client```rust\n{e}```")
-        }
-        handler::db_sync(moved_db, moved_cli.get_path("reaction_count")).await;
-    });
-
-    // Set the ctrl+c handler
-    let handle = Handle::current();
-    let abort = vec![a.abort_handle(), b.abort_handle()];
-    ctrlc::set_handler(move || {
-        warn!("terminating floppa");
-        handle.block_on(handler::db_sync(db.clone(), cli.get_path("reaction_count")));
-        let _enter = handle.enter();
-        for handle in &abort {
-            handle.abort();
-        }
-    })
-    .expect("error setting ctrlc handler");
-
-    let joined = join!(a, b);
-    if let Err(e) = joined.0.and(joined.1) {
-        if !e.is_cancelled() {
-            let msg = format!("Error waiting for tasks: {e}");
-            error!("{}", msg);
+    let mut client_task = tokio::spawn(async move { client.start().await });
+
+    let reason = tokio::select! {
+        reason = SHUTDOWN.requested() => reason,
+        result = &mut client_task => {
+            match result {
+                Ok(Ok(())) => warn!("Client stopped unexpectedly"),
+                Ok(Err(e)) => error!("Fatal error running client```rust\n{e}```"),
+                Err(e) => error!("Error waiting for client```rust\n{e}```"),
+            }
+            ExitReason::ClientError
         }
+    };
+
+    // Stop taking new commands, and give the running ones a chance to finish
+    warn!("terminating floppa");
+    SHUTDOWN
+        .wait_for_commands(Duration::from_secs(cfg.shutdown_timeout))
+        .await;
+
+    shard_manager.shutdown_all().await;
+    if time::timeout(Duration::from_secs(cfg.shutdown_timeout), &mut client_task)
+        .await
+        .is_err()
+    {
+        client_task.abort();
     }
+    sync_task.abort();
+    schedule_task.abort();
+
+    // Write anything left, webhook logs are sent as they happen so there are none to flush
+    FlopDB::flush(&db).await;
+
+    process::exit(reason.code());
 }
 
 #[derive(Parser, Debug, Clone)]
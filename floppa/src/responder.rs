@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use serenity::model::prelude::*;
+
+/// The max length of a regex once compiled, so triggers can't use up loads of memory
+const REGEX_SIZE_LIMIT: usize = 1 << 16;
+
+/// Something sent or done in response to messages that match a trigger
+#[derive(Debug)]
+pub struct Responder {
+    /// The id of the responder in the db
+    pub id: i64,
+    /// Who added the responder
+    pub owner: UserId,
+    /// The guild the responder works in, or every guild if [`None`]
+    pub guild: Option<GuildId>,
+    /// The channel the responder works in, or every channel if [`None`]
+    pub channel: Option<ChannelId>,
+    /// What messages the responder responds to
+    pub trigger: Trigger,
+    /// What the responder does
+    pub action: ResponderAction,
+    /// How long to wait before responding in the same channel again
+    pub cooldown: Duration,
+    /// If its reactions are added to the flop counters
+    pub counts: bool,
+    /// When the responder last responded in each channel
+    last_fired: Mutex<HashMap<ChannelId, Instant>>,
+}
+
+/// What messages a [`Responder`] responds to
+#[derive(Debug, Clone)]
+pub enum Trigger {
+    /// The text appears anywhere, ignoring whitespace and repeated letters, see [`normalise`]
+    Substring(String),
+    /// The text appears as whole words, ignoring case
+    Word(String),
+    /// The regex matches
+    Regex(Regex),
+}
+
+/// What a [`Responder`] does when it is triggered
+#[derive(Debug, Clone)]
+pub enum ResponderAction {
+    /// Reacts with each of the emoji
+    React(Vec<ReactionType>),
+    /// Replies with the text
+    Reply(String),
+    /// Runs a command as the person who sent the message, without the prefix
+    Command(String),
+}
+
+/// An error making a [`Responder`], meant to be shown to the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponderError(String);
+
+impl Display for ResponderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ResponderError {}
+
+impl Responder {
+    pub fn new(
+        id: i64,
+        owner: UserId,
+        guild: Option<GuildId>,
+        channel: Option<ChannelId>,
+        trigger: Trigger,
+        action: ResponderAction,
+        cooldown: Duration,
+    ) -> Self {
+        Self {
+            id,
+            owner,
+            guild,
+            channel,
+            trigger,
+            action,
+            cooldown,
+            counts: false,
+            last_fired: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets if the responder's reactions are counted, see [`Responder::counts`]
+    pub fn with_counts(mut self, counts: bool) -> Self {
+        self.counts = counts;
+        self
+    }
+
+    /// Checks if the responder works where the message was sent
+    pub fn in_scope(&self, msg: &Message) -> bool {
+        self.guild.is_none_or(|x| msg.guild_id == Some(x))
+            && self.channel.is_none_or(|x| msg.channel_id == x)
+    }
+
+    /// Checks if the responder is off cooldown in a channel, starting the cooldown if it is
+    pub fn try_fire(&self, channel: ChannelId) -> bool {
+        let mut lock = self.last_fired.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match lock.get(&channel) {
+            Some(last) if now.duration_since(*last) < self.cooldown => false,
+            _ => {
+                lock.insert(channel, now);
+                true
+            }
+        }
+    }
+}
+
+impl Trigger {
+    /// Builds a trigger from its kind and text, as stored in the db
+    pub fn parse(kind: &str, text: &str) -> Result<Self, ResponderError> {
+        if text.is_empty() {
+            return Err(ResponderError("Triggers can't be empty".to_string()));
+        }
+        match kind {
+            "substring" => Ok(Self::Substring(text.to_owned())),
+            "word" => Ok(Self::Word(text.to_owned())),
+            "regex" => regex::RegexBuilder::new(text)
+                .size_limit(REGEX_SIZE_LIMIT)
+                .build()
+                .map(Self::Regex)
+                .map_err(|e| ResponderError(format!("Invalid regex: {e}"))),
+            _ => Err(ResponderError(format!(
+                "Unknown trigger `{kind}`, expected `substring`, `word` or `regex`"
+            ))),
+        }
+    }
+
+    /// The name of the trigger stored in the db
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Substring(_) => "substring",
+            Self::Word(_) => "word",
+            Self::Regex(_) => "regex",
+        }
+    }
+
+    /// The text of the trigger stored in the db
+    pub fn pattern(&self) -> &str {
+        match self {
+            Self::Substring(text) | Self::Word(text) => text,
+            Self::Regex(regex) => regex.as_str(),
+        }
+    }
+
+    /// Checks if a message matches, `normalised` being the message after [`normalise`]
+    pub fn matches(&self, content: &str, normalised: &str) -> bool {
+        match self {
+            Self::Substring(text) => normalised.contains(&normalise(text)),
+            Self::Word(word) => {
+                let content = content.to_lowercase();
+                let word = word.to_lowercase();
+                content.match_indices(&word).any(|(i, _)| {
+                    let before = content[..i].chars().next_back();
+                    let after = content[i + word.len()..].chars().next();
+                    !before.is_some_and(char::is_alphanumeric)
+                        && !after.is_some_and(char::is_alphanumeric)
+                })
+            }
+            Self::Regex(regex) => regex.is_match(content),
+        }
+    }
+}
+
+impl ResponderAction {
+    /// Builds an action from its kind and body, as stored in the db
+    pub fn parse(kind: &str, body: &str) -> Result<Self, ResponderError> {
+        if body.is_empty() {
+            return Err(ResponderError("Actions can't be empty".to_string()));
+        }
+        match kind {
+            "react" => body
+                .split_whitespace()
+                .map(|x| {
+                    ReactionType::try_from(x)
+                        .map_err(|e| ResponderError(format!("`{x}` is not an emoji: {e}")))
+                })
+                .collect::<Result<_, _>>()
+                .map(Self::React),
+            "reply" => Ok(Self::Reply(body.to_owned())),
+            "command" => Ok(Self::Command(body.to_owned())),
+            _ => Err(ResponderError(format!(
+                "Unknown action `{kind}`, expected `react`, `reply` or `command`"
+            ))),
+        }
+    }
+
+    /// The name of the action stored in the db
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::React(_) => "react",
+            Self::Reply(_) => "reply",
+            Self::Command(_) => "command",
+        }
+    }
+
+    /// The body of the action stored in the db
+    pub fn body(&self) -> String {
+        match self {
+            Self::React(emoji) => emoji
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            Self::Reply(body) | Self::Command(body) => body.clone(),
+        }
+    }
+}
+
+/// Removes whitespace and repeated characters, so `f l o o p` matches `flop`
+pub fn normalise(text: &str) -> String {
+    let mut chars: Vec<char> = text.chars().filter(|x| !x.is_whitespace()).collect();
+    chars.dedup();
+    chars.into_iter().collect()
+}
@@ -0,0 +1,412 @@
+use std::{fmt::Display, sync::Arc, time::Duration};
+
+use serenity::model::prelude::*;
+use time::{Date, OffsetDateTime, Time};
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::handler::FlopHandler;
+
+/// How often the scheduler checks for schedules that are due
+const SCHEDULE_TICK: Duration = Duration::from_secs(15);
+/// How many days ahead a cron expression is searched for its next run
+const CRON_SEARCH_DAYS: i64 = 366 * 5;
+
+/// A message or command to be sent in a channel at a given time, optionally repeating
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    /// The id of the schedule in the db
+    pub id: i64,
+    /// Who added the schedule, commands are ran as them
+    pub owner: UserId,
+    /// The guild the channel is in, if it is in one
+    pub guild: Option<GuildId>,
+    /// Where the schedule is sent
+    pub channel: ChannelId,
+    /// What gets sent
+    pub action: ScheduleAction,
+    /// The cron expression the schedule repeats on, if it does
+    pub cron: Option<String>,
+    /// Unix timestamp of when the schedule is next ran
+    pub next_run: i64,
+    /// Paused schedules are kept but not ran
+    pub paused: bool,
+}
+
+/// What a [`Schedule`] does when it is ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleAction {
+    /// Posts the text as a message
+    Message(String),
+    /// Runs a command, without the prefix
+    Command(String),
+}
+
+impl ScheduleAction {
+    /// Builds an action from how it is stored in the db
+    pub fn from_parts(kind: &str, body: String) -> Option<Self> {
+        match kind {
+            "message" => Some(Self::Message(body)),
+            "command" => Some(Self::Command(body)),
+            _ => None,
+        }
+    }
+
+    /// The name of the action stored in the db
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Message(_) => "message",
+            Self::Command(_) => "command",
+        }
+    }
+
+    /// The text or command of the action
+    pub fn body(&self) -> &str {
+        match self {
+            Self::Message(body) | Self::Command(body) => body,
+        }
+    }
+}
+
+/// A message to send to a user at a given time
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    /// The id of the reminder in the db
+    pub id: i64,
+    /// Who gets reminded
+    pub user: UserId,
+    /// The guild the reminder was made in, if it was made in one
+    pub guild: Option<GuildId>,
+    /// The channel the reminder was made in
+    pub channel: ChannelId,
+    /// The message that made the reminder, which gets replied to
+    pub message: MessageId,
+    /// What to remind the user of
+    pub text: String,
+    /// Unix timestamp of when the reminder was made
+    pub added: i64,
+    /// Unix timestamp of when the reminder is due
+    pub due: i64,
+    /// If the reminder is sent in DMs, instead of as a reply
+    pub dm: bool,
+}
+
+/// Runs any schedules and sends any reminders that are due, until floppa stops
+///
+/// Schedules that were due while floppa was offline are ran once when it starts again,
+/// and reminders are all sent late
+pub async fn schedule_loop(handler: Arc<FlopHandler>) {
+    let mut interval = interval(SCHEDULE_TICK);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        handler.run_due_schedules().await;
+        handler.send_due_reminders().await;
+    }
+}
+
+/// A parsed cron expression, in the standard 5 field `minute hour day month weekday` form
+///
+/// Times are in UTC, and like most crons, if both the day of month and weekday are restricted,
+/// either one matching is enough
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// If the day of month field was `*`
+    any_day: bool,
+    /// If the weekday field was `*`
+    any_weekday: bool,
+}
+
+/// An error parsing a [`Cron`], meant to be shown to the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronError(String);
+
+impl Display for CronError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CronError {}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl Cron {
+    /// Parses a cron expression, or one of `@hourly`, `@daily`, `@weekly`, `@monthly` or `@yearly`
+    pub fn parse(expr: &str) -> Result<Self, CronError> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            x if x.starts_with('@') => return Err(CronError(format!("Unknown macro `{x}`"))),
+            x => x,
+        };
+
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(CronError(format!(
+                "Expected 5 fields (minute hour day month weekday), found {}",
+                fields.len()
+            )));
+        };
+
+        // Sunday can be 0 or 7
+        let weekdays = parse_field(weekday, 0, 7, &WEEKDAY_NAMES, "weekday")?;
+        let weekdays = (weekdays | weekdays >> 7) & 0x7f;
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[], "minute")?,
+            hours: parse_field(hour, 0, 23, &[], "hour")? as u32,
+            days: parse_field(day, 1, 31, &[], "day")? as u32,
+            months: parse_field(month, 1, 12, &MONTH_NAMES, "month")? as u16,
+            weekdays: weekdays as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// Gets the first time this matches strictly after `after`, as a unix timestamp
+    ///
+    /// Returns [`None`] if it never matches, e.g. for the 31st of February
+    pub fn next_after(&self, after: i64) -> Option<i64> {
+        let start = OffsetDateTime::from_unix_timestamp(after).ok()? + time::Duration::MINUTE;
+        let mut date = start.date();
+        let mut from = (start.hour(), start.minute());
+
+        for _ in 0..CRON_SEARCH_DAYS {
+            if self.matches_date(date) {
+                if let Some((hour, minute)) = self.first_time_from(from) {
+                    let time = Time::from_hms(hour, minute, 0).ok()?;
+                    return Some(date.with_time(time).assume_utc().unix_timestamp());
+                }
+            }
+            date = date.next_day()?;
+            from = (0, 0);
+        }
+        None
+    }
+
+    fn matches_date(&self, date: Date) -> bool {
+        if self.months & (1 << u8::from(date.month())) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().number_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// Finds the first matching hour and minute at or after `from` in a day
+    fn first_time_from(&self, (from_hour, from_minute): (u8, u8)) -> Option<(u8, u8)> {
+        (from_hour..24)
+            .filter(|hour| self.hours & (1 << hour) != 0)
+            .find_map(|hour| {
+                let first = if hour == from_hour { from_minute } else { 0 };
+                (first..60)
+                    .find(|minute| self.minutes & (1 << minute) != 0)
+                    .map(|minute| (hour, minute))
+            })
+    }
+}
+
+/// Parses a comma separated list of `*`, values, ranges and steps into a bitmask
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    what: &str,
+) -> Result<u64, CronError> {
+    let value = |text: &str| -> Result<u32, CronError> {
+        let lower = text.to_lowercase();
+        if let Some(i) = names.iter().position(|x| *x == lower) {
+            // Names start from the lowest value
+            return Ok(i as u32 + min);
+        }
+        match text.parse::<u32>() {
+            Ok(x) if (min..=max).contains(&x) => Ok(x),
+            _ => Err(CronError(format!(
+                "`{text}` is not a valid {what}, expected {min}-{max}"
+            ))),
+        }
+    };
+
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(CronError(format!("`{step}` is not a valid step"))),
+            },
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else {
+            let start = value(range)?;
+            // `5/15` means every 15 from 5
+            (start, if part.contains('/') { max } else { start })
+        };
+
+        if start > end {
+            return Err(CronError(format!("`{range}` is a backwards range")));
+        }
+        for x in (start..=end).step_by(step as usize) {
+            mask |= 1 << x;
+        }
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Month;
+
+    use super::*;
+
+    /// A UTC time as a unix timestamp
+    fn at(year: i32, month: u8, day: u8, hour: u8, minute: u8) -> i64 {
+        Date::from_calendar_date(year, Month::try_from(month).unwrap(), day)
+            .unwrap()
+            .with_hms(hour, minute, 0)
+            .unwrap()
+            .assume_utc()
+            .unix_timestamp()
+    }
+
+    fn cron(expr: &str) -> Cron {
+        Cron::parse(expr).unwrap_or_else(|e| panic!("`{expr}` gave {e}"))
+    }
+
+    fn bits(mask: u64) -> Vec<u32> {
+        (0..64).filter(|x| mask & (1 << x) != 0).collect()
+    }
+
+    #[test]
+    fn parses_ranges_steps_and_lists() {
+        let cron = cron("*/15 9-17 * jan,jun-aug mon-fri");
+        assert_eq!(bits(cron.minutes), [0, 15, 30, 45]);
+        assert_eq!(bits(cron.hours.into()), (9..=17).collect::<Vec<_>>());
+        assert_eq!(bits(cron.months.into()), [1, 6, 7, 8]);
+        assert_eq!(bits(cron.weekdays.into()), [1, 2, 3, 4, 5]);
+        assert!(cron.any_day && !cron.any_weekday);
+
+        assert_eq!(
+            bits(parse_field("1,5-7,20/10", 0, 59, &[], "minute").unwrap()),
+            [1, 5, 6, 7, 20, 30, 40, 50]
+        );
+        assert_eq!(bits(Cron::parse("0 0 * * 7").unwrap().weekdays.into()), [0]);
+        assert_eq!(Cron::parse("@daily"), Cron::parse("0 0 * * *"));
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        for expr in [
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * *",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "* * * * funday",
+            "@fortnightly",
+        ] {
+            assert!(Cron::parse(expr).is_err(), "{expr}");
+        }
+    }
+
+    #[test]
+    fn finds_the_next_time() {
+        let daily = cron("30 9 * * *");
+        assert_eq!(
+            daily.next_after(at(2024, 5, 1, 9, 0)),
+            Some(at(2024, 5, 1, 9, 30))
+        );
+        // Strictly after, so a match at the same time is skipped
+        assert_eq!(
+            daily.next_after(at(2024, 5, 1, 9, 30)),
+            Some(at(2024, 5, 2, 9, 30))
+        );
+        assert_eq!(
+            daily.next_after(at(2024, 5, 1, 10, 0)),
+            Some(at(2024, 5, 2, 9, 30))
+        );
+
+        let quarters = cron("*/15 * * * *");
+        assert_eq!(
+            quarters.next_after(at(2024, 5, 1, 10, 7)),
+            Some(at(2024, 5, 1, 10, 15))
+        );
+        assert_eq!(
+            quarters.next_after(at(2024, 5, 1, 23, 50)),
+            Some(at(2024, 5, 2, 0, 0))
+        );
+    }
+
+    #[test]
+    fn matches_either_day_field() {
+        // Friday the 13th style crons run on every Friday and every 13th
+        let either = cron("0 0 13 * fri");
+        assert_eq!(
+            either.next_after(at(2024, 5, 1, 0, 0)),
+            Some(at(2024, 5, 3, 0, 0))
+        );
+        assert_eq!(
+            either.next_after(at(2024, 5, 10, 0, 0)),
+            Some(at(2024, 5, 13, 0, 0))
+        );
+
+        // Only one field restricted means only that one counts
+        let mondays = cron("0 8 * * mon");
+        assert_eq!(
+            mondays.next_after(at(2024, 5, 1, 0, 0)),
+            Some(at(2024, 5, 6, 8, 0))
+        );
+        let thirteenths = cron("0 0 13 * *");
+        assert_eq!(
+            thirteenths.next_after(at(2024, 5, 3, 0, 0)),
+            Some(at(2024, 5, 13, 0, 0))
+        );
+    }
+
+    #[test]
+    fn rolls_over_months_and_years() {
+        let last = cron("0 12 31 * *");
+        // April has no 31st
+        assert_eq!(
+            last.next_after(at(2024, 4, 15, 0, 0)),
+            Some(at(2024, 5, 31, 12, 0))
+        );
+
+        let monthly = cron("@monthly");
+        assert_eq!(
+            monthly.next_after(at(2024, 12, 15, 0, 0)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+
+        let leap = cron("0 0 29 2 *");
+        assert_eq!(
+            leap.next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+
+        assert_eq!(cron("0 0 31 2 *").next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+}
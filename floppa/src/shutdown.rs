@@ -0,0 +1,158 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::{sync::Notify, time};
+use tracing::{info, warn};
+
+/// The global shutdown coordinator
+pub static SHUTDOWN: Shutdown = Shutdown::new();
+
+/// Why floppa is stopping, which decides the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// Stopped by ctrl-c or SIGTERM
+    Signal,
+    /// Stopped by an admin
+    Shutdown,
+    /// Stopped by an admin, asking to be started again
+    Restart,
+    /// The discord client stopped with an error
+    ClientError,
+}
+
+impl ExitReason {
+    /// The exit code for the process
+    ///
+    /// Restarts use `EX_TEMPFAIL`, so a supervisor can start floppa again on that code only
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Signal | Self::Shutdown => 0,
+            Self::Restart => 75,
+            Self::ClientError => 1,
+        }
+    }
+}
+
+/// Coordinates stopping floppa, so new commands are refused and running ones can finish
+#[derive(Debug)]
+pub struct Shutdown {
+    /// Set once shutdown has started
+    stopping: AtomicBool,
+    /// The first reason shutdown was asked for
+    reason: Mutex<Option<ExitReason>>,
+    /// Notified when shutdown is asked for
+    requested: Notify,
+    /// How many commands are running
+    in_flight: AtomicUsize,
+    /// Notified when the last running command finishes
+    idle: Notify,
+}
+
+impl Shutdown {
+    const fn new() -> Self {
+        Self {
+            stopping: AtomicBool::new(false),
+            reason: Mutex::new(None),
+            requested: Notify::const_new(),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::const_new(),
+        }
+    }
+
+    /// Asks for floppa to stop, only the first reason given is kept
+    pub fn request(&self, reason: ExitReason) {
+        let mut lock = self.reason.lock().unwrap_or_else(|e| e.into_inner());
+        if lock.is_none() {
+            info!("Shutdown requested: {reason:?}");
+            *lock = Some(reason);
+        }
+        drop(lock);
+        self.stopping.store(true, Ordering::SeqCst);
+        self.requested.notify_one();
+    }
+
+    /// Waits until shutdown is asked for, by [`Shutdown::request`] or a signal
+    pub async fn requested(&self) -> ExitReason {
+        loop {
+            if let Some(reason) = *self.reason.lock().unwrap_or_else(|e| e.into_inner()) {
+                return reason;
+            }
+            tokio::select! {
+                _ = self.requested.notified() => {}
+                _ = signal() => self.request(ExitReason::Signal),
+            }
+        }
+    }
+
+    /// Marks a command as running until the guard is dropped
+    ///
+    /// Returns [`None`] once shutdown has started, so the command shouldn't run
+    pub fn start_command(&self) -> Option<CommandGuard<'_>> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = CommandGuard(self);
+        // Checked after counting it, so a command can't slip past `wait_for_commands`
+        if self.stopping.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Waits for running commands to finish, giving up after `timeout`
+    pub async fn wait_for_commands(&self, timeout: Duration) {
+        self.stopping.store(true, Ordering::SeqCst);
+        let wait = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        if time::timeout(timeout, wait).await.is_err() {
+            warn!(
+                "Gave up waiting for {} commands to finish",
+                self.in_flight.load(Ordering::SeqCst)
+            );
+        }
+    }
+}
+
+/// Keeps a command counted as running, see [`Shutdown::start_command`]
+#[derive(Debug)]
+pub struct CommandGuard<'a>(&'a Shutdown);
+
+impl Drop for CommandGuard<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Waits for ctrl-c, or SIGTERM on unix
+async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("Error listening for SIGTERM: `{e}`"),
+        }
+    }
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Error listening for ctrl-c: `{e}`");
+        std::future::pending::<()>().await;
+    }
+}
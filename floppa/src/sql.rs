@@ -1,11 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 
 use serde::{Deserialize, Serialize};
 use serenity::{
-    all::GuildId,
+    all::{ChannelId, GuildId, MessageId},
     futures::TryStreamExt,
     model::{id::UserId, Timestamp},
 };
@@ -13,17 +14,36 @@ use sqlx::{
     sqlite::{SqliteConnectOptions, SqlitePoolOptions},
     FromRow, Pool, Sqlite,
 };
-use tokio::{sync::Mutex, time::Instant};
-use tracing::{error, info, warn};
+use tokio::{
+    sync::{Mutex, RwLock},
+    time::Instant,
+};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     command::{self, ExtendedCommand},
+    responder::{Responder, ResponderAction, Trigger},
+    schedule::{Reminder, Schedule, ScheduleAction},
     Cli, FlopResult,
 };
 
 const COMMAND_SEARCH_DEPTH_LIMIT: usize = 64;
+/// Changes to the schema applied in order after `assets/schema.sql`, tracked with `user_version`
+const MIGRATIONS: &[&str] = &[
+    include_str!("../assets/migrations/001_command_stats.sql"),
+    include_str!("../assets/migrations/002_counters.sql"),
+    include_str!("../assets/migrations/003_schedules.sql"),
+    include_str!("../assets/migrations/004_reminders.sql"),
+    include_str!("../assets/migrations/005_responders.sql"),
+    include_str!("../assets/migrations/006_reactions.sql"),
+    include_str!("../assets/migrations/007_github.sql"),
+    include_str!("../assets/migrations/008_flop_counts.sql"),
+    include_str!("../assets/migrations/009_last_used.sql"),
+];
 /// The name of the default root registry
 pub const ROOT_REGISTRY: &str = "root";
+/// The name of the counter for how many reactions there have been since the last reset
+pub const REACTION_COUNTER: &str = "reactions";
 
 #[derive(Debug)]
 pub struct FlopDB {
@@ -41,6 +61,12 @@ pub struct FlopDB {
     user_roles: HashMap<UserId, (Vec<FlopRole>, SyncState)>,
     /// List of guilds and their root registry
     guilds: HashMap<GuildId, String>,
+    /// Held while flushing, see [`FlopDB::flush`]
+    sync_lock: Arc<Mutex<()>>,
+    /// Auto-responders, these are written to the db straight away
+    responders: Vec<Arc<Responder>>,
+    /// The GitHub repo issue references are expanded from in each guild
+    github_repos: HashMap<GuildId, String>,
 }
 
 impl FlopDB {
@@ -60,6 +86,7 @@ impl FlopDB {
         sqlx::query_file!("assets/schema.sql")
             .execute(&pool)
             .await?;
+        migrate(&pool).await?;
 
         let mut commands: HashMap<(String, String), Arc<Mutex<CommandEntry>>> = HashMap::new();
         let mut rows = sqlx::query_file!("assets/get_commands.sql").fetch(&pool);
@@ -69,6 +96,7 @@ impl FlopDB {
             // Parse and "transform" the values
             let owner = UserId::from(row.owner as u64);
             let added = row.added.unwrap_or_default();
+            let editor = row.editor.map(|x| UserId::from(x as u64));
             let data = &row.data.unwrap_or_default();
             // TODO maybe move to a seperate class?
             let key = (row.registry.clone(), row.name.clone());
@@ -79,7 +107,11 @@ impl FlopDB {
                         "Error constructing command: {} in registry {}\n{e}",
                         row.name, row.registry
                     );
-                    continue;
+                    // Keep the row around so its data isn't lost
+                    CmdNode::Broken {
+                        data: data.clone(),
+                        error: format!("{e}"),
+                    }
                 }
             };
 
@@ -91,6 +123,10 @@ impl FlopDB {
                 ty: row.ty,
                 added,
                 registry: row.registry,
+                uses: row.uses,
+                last_used: row.last_used,
+                editor,
+                edited: row.edited,
             };
 
             commands.insert(key, Arc::new(Mutex::new(cmd)));
@@ -137,6 +173,41 @@ impl FlopDB {
             }
         }
 
+        let mut responders = Vec::new();
+        let responder_rows = sqlx::query!(
+            r#"SELECT id, owner, guild, channel, trigger, pattern, action, body, cooldown,
+                 counts as "counts: bool"
+               FROM responders ORDER BY id;"#
+        )
+        .fetch_all(&pool)
+        .await?;
+        for row in responder_rows {
+            let trigger = Trigger::parse(&row.trigger, &row.pattern);
+            let action = ResponderAction::parse(&row.action, &row.body);
+            match (trigger, action) {
+                (Ok(trigger), Ok(action)) => responders.push(Arc::new(
+                    Responder::new(
+                        row.id,
+                        UserId::from(row.owner as u64),
+                        row.guild.map(|x| GuildId::from(x as u64)),
+                        row.channel.map(|x| ChannelId::from(x as u64)),
+                        trigger,
+                        action,
+                        Duration::from_secs(row.cooldown as u64),
+                    )
+                    .with_counts(row.counts),
+                )),
+                (Err(e), _) | (_, Err(e)) => warn!("Error loading responder {}: {e}", row.id),
+            }
+        }
+
+        let github_repos = sqlx::query!("SELECT guild, repo FROM github_repos;")
+            .fetch_all(&pool)
+            .await?
+            .into_iter()
+            .map(|x| (GuildId::from(x.guild as u64), x.repo))
+            .collect();
+
         Ok(Self {
             pool,
             commands,
@@ -148,6 +219,9 @@ impl FlopDB {
             dirty_commands: HashSet::new(),
             user_roles,
             guilds,
+            sync_lock: Arc::new(Mutex::new(())),
+            responders,
+            github_repos,
         })
     }
 
@@ -172,6 +246,10 @@ impl FlopDB {
             added: Timestamp::now().unix_timestamp(),
             registry: registry.clone(),
             node: cmd,
+            uses: 0,
+            last_used: None,
+            editor: None,
+            edited: None,
         };
         self.dirty_commands.insert((registry.clone(), name.clone()));
         self.commands
@@ -189,65 +267,108 @@ impl FlopDB {
         }
     }
 
+    /// Writes all the changes made in memory to the db straight away
+    ///
+    /// The write lock is only held while collecting and requeueing changes,
+    /// anything that fails to be written is kept to be retried on the next flush
+    pub async fn flush(data: &RwLock<FlopDB>) {
+        // Only one flush at a time, so new commands can't be inserted twice
+        let sync_lock = data.read().await.sync_lock.clone();
+        let _sync_guard = sync_lock.lock().await;
+
+        let mut lock = data.write().await;
+        let dirty = lock.drain_dirty();
+        let removed = lock.drain_removed();
+        let roles = lock.drain_roles();
+        drop(lock);
+
+        let failed = data.read().await.sync(dirty, removed, roles).await;
+        data.write().await.requeue(failed);
+    }
+
+    /// Writes the given changes to the db, returning the ones that failed
     pub async fn sync(
         &self,
         dirty: HashSet<(String, String)>,
         delete: Vec<i64>,
         roles: Vec<(UserId, SyncState)>,
-    ) -> FlopResult<()> {
-        if dirty.is_empty() && delete.is_empty() {
+    ) -> Unsynced {
+        if dirty.is_empty() && delete.is_empty() && roles.is_empty() {
             // No point doing all of this if there is nothing to act on
-            info!("Nothing to sync");
-            return Ok(());
+            debug!("Nothing to sync");
+            return Unsynced::default();
         }
         let start = Instant::now();
+
+        let mut failed = Unsynced::default();
+        if let Err(e) = self.sync_tx(&dirty, &delete, &roles, &mut failed).await {
+            error!("Error syncing to disk```rust\n{e}```");
+            // Nothing was committed, so everything has to be tried again
+            return Unsynced {
+                commands: dirty,
+                removed: delete,
+                roles,
+            };
+        }
+
+        // Write info out
+        info!("Synced to DB in {:?}", start.elapsed());
+        failed
+    }
+
+    /// Writes changes in a single transaction, recording the rows that fail in `failed`
+    async fn sync_tx(
+        &self,
+        dirty: &HashSet<(String, String)>,
+        delete: &[i64],
+        roles: &[(UserId, SyncState)],
+        failed: &mut Unsynced,
+    ) -> FlopResult<()> {
         // Start a transaction
         let mut tx = self.pool.begin().await?;
+        // Ids of new commands, only given to them once the transaction is committed
+        let mut new_ids = Vec::new();
 
         // every command to be synced
         for key in dirty {
-            let Some(cmd) = self.commands.get(&key) else {
+            let Some(cmd) = self.commands.get(key) else {
                 continue;
             };
 
             // Get a lock on the command
-            let mut cmd_lock = cmd.lock().await;
+            let cmd_lock = cmd.lock().await;
+            // Broken commands are left as they are in the db, so they can be retried later
+            if matches!(cmd_lock.node, CmdNode::Broken { .. }) {
+                continue;
+            }
 
-            if let Some(id) = cmd_lock.id {
-                // If the command was editied (previously had an id)
+            // Process and get data
+            let reg_id = self.registries.get(&cmd_lock.registry).map_or(1, |x| x.id);
+            let data = cmd_lock.node.save();
+            let owner = cmd_lock.owner.get() as i64;
+            let editor = cmd_lock.editor.map(|x| x.get() as i64);
 
-                // Process and get data
-                let reg_id = self.registries.get(&cmd_lock.registry).map_or(1, |x| x.id);
-                let data = cmd_lock.node.save();
-                let owner = cmd_lock.owner.get() as i64;
-                // Construct the actual query
-                let res = sqlx::query_file!(
+            let res = if let Some(id) = cmd_lock.id {
+                // If the command was editied (previously had an id)
+                sqlx::query_file!(
                     "assets/update_command.sql",
                     cmd_lock.name,
                     owner,
                     cmd_lock.ty,
                     reg_id,
                     data,
+                    cmd_lock.uses,
+                    cmd_lock.last_used,
+                    editor,
+                    cmd_lock.edited,
                     id
                 )
                 .execute(&mut *tx)
-                .await;
-                // Handle Error
-                if let Err(e) = res {
-                    error!(
-                        "Error saving command {}:{}```rust\n{e}```",
-                        cmd_lock.registry, cmd_lock.name
-                    )
-                }
+                .await
+                .map(|_| ())
             } else {
                 // If the command doesnt have an id (is new)
-
-                // Process data
-                let reg_id = self.registries.get(&cmd_lock.registry).map_or(1, |x| x.id);
-                let data = cmd_lock.node.save();
-                let owner = cmd_lock.owner.get() as i64;
-                // Construct the query
-                let res = sqlx::query_file!(
+                sqlx::query_file!(
                     "assets/add_command.sql",
                     cmd_lock.name,
                     owner,
@@ -255,17 +376,23 @@ impl FlopDB {
                     reg_id,
                     cmd_lock.added,
                     data,
+                    cmd_lock.uses,
+                    cmd_lock.last_used,
+                    editor,
+                    cmd_lock.edited,
                 )
                 .fetch_one(&mut *tx)
-                .await;
-                // Update the command entry with the id
-                match res {
-                    Ok(id) => cmd_lock.id = Some(id.id),
-                    Err(e) => error!(
-                        "Error saving command {}:{}```rust\n{e}```",
-                        cmd_lock.registry, cmd_lock.name
-                    ),
-                }
+                .await
+                .map(|row| new_ids.push((cmd.clone(), row.id)))
+            };
+
+            // Handle Error
+            if let Err(e) = res {
+                error!(
+                    "Error saving command {}:{}```rust\n{e}```",
+                    cmd_lock.registry, cmd_lock.name
+                );
+                failed.commands.insert(key.clone());
             }
         }
 
@@ -278,62 +405,102 @@ impl FlopDB {
             // handle errors
             if let Err(e) = res {
                 error!("Error deleting command {id}```rust\n{e}```");
+                failed.removed.push(*id);
             }
         }
 
         // Sync roles
         // TODO: think about putting this in a seperate place
         for (user, state) in roles {
-            match state {
+            let id = user.get() as i64;
+            let res = match state {
                 SyncState::Dirty => {
-                    let Some((roles, _)) = self.user_roles.get(&user) else {
+                    let Some((roles, _)) = self.user_roles.get(user) else {
                         continue;
                     };
                     let data = rmp_serde::to_vec(roles)?;
-                    let id = user.get() as i64;
-                    let res = sqlx::query!("UPDATE users SET roles = ? WHERE id = ?;", data, id)
+                    sqlx::query!("UPDATE users SET roles = ? WHERE id = ?;", data, id)
                         .execute(&mut *tx)
-                        .await;
-                    if let Err(e) = res {
-                        error!("Error modifying user roles{id}```rust\n{e}```");
-                    }
+                        .await
                 }
                 SyncState::New => {
-                    let Some((roles, _)) = self.user_roles.get(&user) else {
+                    let Some((roles, _)) = self.user_roles.get(user) else {
                         continue;
                     };
                     let data = rmp_serde::to_vec(roles)?;
-                    let id = user.get() as i64;
-                    let res = sqlx::query!("INSERT INTO users(id, roles) VALUES(?, ?);", id, data)
+                    sqlx::query!("INSERT INTO users(id, roles) VALUES(?, ?);", id, data)
                         .execute(&mut *tx)
-                        .await;
-                    if let Err(e) = res {
-                        error!("Error adding user roles{id}```rust\n{e}```");
-                    }
+                        .await
                 }
-                SyncState::Clean => (),
+                SyncState::Clean => continue,
                 SyncState::Deleted => {
-                    let id = user.get() as i64;
-                    let res = sqlx::query!("DELETE FROM users WHERE id = ?;", id)
+                    sqlx::query!("DELETE FROM users WHERE id = ?;", id)
                         .execute(&mut *tx)
-                        .await;
-                    if let Err(e) = res {
-                        error!("Error deleting user roles{id}```rust\n{e}```");
-                    }
+                        .await
                 }
+            };
+            if let Err(e) = res {
+                error!("Error syncing user roles {id}```rust\n{e}```");
+                failed.roles.push((*user, *state));
             }
         }
 
         // Commit the changes to the DB
         tx.commit().await?;
 
-        // Write info out
-        info!("Synced to DB in {:?}", start.elapsed());
+        for (cmd, id) in new_ids {
+            cmd.lock().await.id = Some(id);
+        }
 
         Ok(())
     }
 
-    /// Marks a command to be synced on next cycle
+    /// Queues changes that failed to sync to be tried again
+    fn requeue(&mut self, failed: Unsynced) {
+        self.dirty_commands.extend(failed.commands);
+        self.removed_commands.extend(failed.removed);
+        for (user, state) in failed.roles {
+            match self.user_roles.get_mut(&user) {
+                Some((_, current)) => {
+                    // Newer changes will be synced anyway, unless the user was never added
+                    if *current == SyncState::Clean
+                        || (state == SyncState::New && *current == SyncState::Dirty)
+                    {
+                        *current = state;
+                    }
+                }
+                // Deleted users are dropped from memory, so bring them back until they sync
+                None => {
+                    self.user_roles.insert(user, (Vec::new(), state));
+                }
+            }
+        }
+    }
+
+    /// Writes just the usage of a command straight away, without the rest of it
+    ///
+    /// Commands that haven't been inserted yet are skipped, they are written in full when they are
+    pub async fn record_use(
+        &self,
+        id: Option<i64>,
+        uses: i64,
+        last_used: Option<i64>,
+    ) -> FlopResult<()> {
+        let Some(id) = id else {
+            return Ok(());
+        };
+        sqlx::query!(
+            "UPDATE commands SET uses = ?, last_used = ? WHERE id = ?;",
+            uses,
+            last_used,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Marks a command to be synced on next flush
     pub fn mark_dirty(&mut self, registry: String, name: String) {
         self.dirty_commands.insert((registry, name));
     }
@@ -392,7 +559,7 @@ impl FlopDB {
                 let mut cmd_lock = cmd.lock().await;
                 let node = cmd_lock.get_node();
                 match node {
-                    CmdNode::Cmd(_) => {
+                    CmdNode::Cmd(_) | CmdNode::Broken { .. } => {
                         result.call += " ";
                         result.call += &search_name;
                         result.call = result.call.trim().to_owned();
@@ -437,6 +604,544 @@ impl FlopDB {
         result
     }
 
+    /// Gets the value of a counter, 0 if it has never been added to
+    pub async fn get_counter(&self, name: &str) -> FlopResult<i64> {
+        let value = sqlx::query_scalar!("SELECT value FROM counters WHERE name = ?;", name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(value.unwrap_or_default())
+    }
+
+    /// Sets a counter if it hasn't been set yet
+    pub async fn init_counter(&self, name: &str, value: i64) -> FlopResult<()> {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO counters(name, value) VALUES(?, ?);",
+            name,
+            value
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records the flop reaction to a message from `user`, returning the new count since the last reset
+    ///
+    /// Each message is only counted once, so [`None`] is returned if it already was
+    pub async fn add_reaction(
+        &self,
+        guild: Option<GuildId>,
+        channel: ChannelId,
+        message: MessageId,
+        user: UserId,
+    ) -> FlopResult<Option<i64>> {
+        let guild = guild.map(|x| x.get() as i64);
+        let channel = channel.get() as i64;
+        let message = message.get() as i64;
+        let user = user.get() as i64;
+        let time = Timestamp::now().unix_timestamp();
+
+        let mut tx = self.pool.begin().await?;
+        let inserted = sqlx::query!(
+            "INSERT OR IGNORE INTO reactions(guild, channel, message, user, time)
+             VALUES(?, ?, ?, ?, ?);",
+            guild,
+            channel,
+            message,
+            user,
+            time
+        )
+        .execute(&mut *tx)
+        .await?;
+        if inserted.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let count = sqlx::query_scalar!(
+            "INSERT INTO counters(name, value) VALUES(?, 1)
+             ON CONFLICT(name) DO UPDATE SET value = value + 1
+             RETURNING value;",
+            REACTION_COUNTER
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(count))
+    }
+
+    /// Counts the reactions since the last reset and `since`, in a guild or everywhere
+    pub async fn count_reactions(&self, guild: Option<GuildId>, since: i64) -> FlopResult<i64> {
+        let guild = guild.map(|x| x.get() as i64);
+        Ok(sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM reactions
+             WHERE reset IS NULL AND time >= ?1 AND (?2 IS NULL OR guild = ?2);",
+            since,
+            guild
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .into())
+    }
+
+    /// Gets the users whose messages were reacted to the most since the last reset and `since`
+    pub async fn reaction_users(
+        &self,
+        guild: Option<GuildId>,
+        since: i64,
+        limit: i64,
+    ) -> FlopResult<Vec<(UserId, i64)>> {
+        let guild = guild.map(|x| x.get() as i64);
+        let rows = sqlx::query!(
+            r#"SELECT user, COUNT(*) as "count: i64" FROM reactions
+               WHERE reset IS NULL AND time >= ?1 AND (?2 IS NULL OR guild = ?2)
+               GROUP BY user ORDER BY COUNT(*) DESC LIMIT ?3;"#,
+            since,
+            guild,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|x| (UserId::from(x.user as u64), x.count))
+            .collect())
+    }
+
+    /// Gets the channels with the most reactions since the last reset and `since`
+    pub async fn reaction_channels(
+        &self,
+        guild: Option<GuildId>,
+        since: i64,
+        limit: i64,
+    ) -> FlopResult<Vec<(ChannelId, i64)>> {
+        let guild = guild.map(|x| x.get() as i64);
+        let rows = sqlx::query!(
+            r#"SELECT channel, COUNT(*) as "count: i64" FROM reactions
+               WHERE reset IS NULL AND time >= ?1 AND (?2 IS NULL OR guild = ?2)
+               GROUP BY channel ORDER BY COUNT(*) DESC LIMIT ?3;"#,
+            since,
+            guild,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|x| (ChannelId::from(x.channel as u64), x.count))
+            .collect())
+    }
+
+    /// Archives the current reactions and sets the count back to 0, returning how many there were
+    ///
+    /// The reactions are kept, marked with the id of the reset
+    pub async fn reset_reactions(&self, user: UserId) -> FlopResult<i64> {
+        let user = user.get() as i64;
+        let time = Timestamp::now().unix_timestamp();
+
+        let mut tx = self.pool.begin().await?;
+        let count = sqlx::query_scalar!(
+            "SELECT value FROM counters WHERE name = ?;",
+            REACTION_COUNTER
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or_default();
+        let reset = sqlx::query_scalar!(
+            "INSERT INTO reaction_resets(user, time, count) VALUES(?, ?, ?) RETURNING id;",
+            user,
+            time,
+            count
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query!("UPDATE reactions SET reset = ? WHERE reset IS NULL;", reset)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "UPDATE counters SET value = 0 WHERE name = ?;",
+            REACTION_COUNTER
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(count)
+    }
+
+    /// Adds a schedule straight away, returning its id
+    pub async fn add_schedule(&self, schedule: &Schedule) -> FlopResult<i64> {
+        let owner = schedule.owner.get() as i64;
+        let guild = schedule.guild.map(|x| x.get() as i64);
+        let channel = schedule.channel.get() as i64;
+        let kind = schedule.action.kind();
+        let body = schedule.action.body();
+        Ok(sqlx::query_scalar!(
+            "INSERT INTO schedules(owner, guild, channel, action, body, cron, next_run, paused)
+             VALUES(?, ?, ?, ?, ?, ?, ?, ?)
+             RETURNING id;",
+            owner,
+            guild,
+            channel,
+            kind,
+            body,
+            schedule.cron,
+            schedule.next_run,
+            schedule.paused
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Gets a schedule by its id
+    pub async fn get_schedule(&self, id: i64) -> FlopResult<Option<Schedule>> {
+        let row = sqlx::query_as!(
+            ScheduleRow,
+            r#"SELECT id, owner, guild, channel, action, body, cron, next_run, paused as "paused: bool"
+               FROM schedules WHERE id = ?;"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.and_then(ScheduleRow::into_schedule))
+    }
+
+    /// Gets the schedules sent in a guild, or in a channel outside of guilds, sorted by id
+    pub async fn list_schedules(
+        &self,
+        guild: Option<GuildId>,
+        channel: ChannelId,
+    ) -> FlopResult<Vec<Schedule>> {
+        let guild = guild.map(|x| x.get() as i64);
+        let channel = channel.get() as i64;
+        let rows = sqlx::query_as!(
+            ScheduleRow,
+            r#"SELECT id, owner, guild, channel, action, body, cron, next_run, paused as "paused: bool"
+               FROM schedules WHERE guild = ?1 OR (?1 IS NULL AND channel = ?2)
+               ORDER BY id;"#,
+            guild,
+            channel
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(ScheduleRow::into_schedule)
+            .collect())
+    }
+
+    /// Counts the schedules a user owns, wherever they are
+    pub async fn count_user_schedules(&self, user: UserId) -> FlopResult<i64> {
+        let user = user.get() as i64;
+        Ok(sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count: i64" FROM schedules WHERE owner = ?;"#,
+            user
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Gets the schedules that aren't paused and should have ran by `now`
+    pub async fn due_schedules(&self, now: i64) -> FlopResult<Vec<Schedule>> {
+        let rows = sqlx::query_as!(
+            ScheduleRow,
+            r#"SELECT id, owner, guild, channel, action, body, cron, next_run, paused as "paused: bool"
+               FROM schedules WHERE paused = 0 AND next_run <= ?
+               ORDER BY next_run;"#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(ScheduleRow::into_schedule)
+            .collect())
+    }
+
+    /// Sets when a schedule next runs, and if it is paused
+    pub async fn update_schedule(&self, id: i64, next_run: i64, paused: bool) -> FlopResult<()> {
+        sqlx::query!(
+            "UPDATE schedules SET next_run = ?, paused = ? WHERE id = ?;",
+            next_run,
+            paused,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes a schedule, returning if it existed
+    pub async fn delete_schedule(&self, id: i64) -> FlopResult<bool> {
+        let result = sqlx::query!("DELETE FROM schedules WHERE id = ?;", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Adds a reminder straight away, returning its id
+    pub async fn add_reminder(&self, reminder: &Reminder) -> FlopResult<i64> {
+        let user = reminder.user.get() as i64;
+        let guild = reminder.guild.map(|x| x.get() as i64);
+        let channel = reminder.channel.get() as i64;
+        let message = reminder.message.get() as i64;
+        Ok(sqlx::query_scalar!(
+            "INSERT INTO reminders(user, guild, channel, message, text, added, due, dm)
+             VALUES(?, ?, ?, ?, ?, ?, ?, ?)
+             RETURNING id;",
+            user,
+            guild,
+            channel,
+            message,
+            reminder.text,
+            reminder.added,
+            reminder.due,
+            reminder.dm
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Gets a user's pending reminders, soonest first
+    pub async fn user_reminders(&self, user: UserId) -> FlopResult<Vec<Reminder>> {
+        let user = user.get() as i64;
+        let rows = sqlx::query_as!(
+            ReminderRow,
+            r#"SELECT id, user, guild, channel, message, text, added, due, dm as "dm: bool"
+               FROM reminders WHERE user = ? ORDER BY due;"#,
+            user
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Reminder::from).collect())
+    }
+
+    /// Gets the reminders that should have been sent by `now`
+    pub async fn due_reminders(&self, now: i64) -> FlopResult<Vec<Reminder>> {
+        let rows = sqlx::query_as!(
+            ReminderRow,
+            r#"SELECT id, user, guild, channel, message, text, added, due, dm as "dm: bool"
+               FROM reminders WHERE due <= ? ORDER BY due;"#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Reminder::from).collect())
+    }
+
+    /// Deletes a reminder, returning if it existed
+    ///
+    /// If `user` is given, only their own reminder is deleted
+    pub async fn delete_reminder(&self, id: i64, user: Option<UserId>) -> FlopResult<bool> {
+        let user = user.map(|x| x.get() as i64);
+        let result = sqlx::query!(
+            "DELETE FROM reminders WHERE id = ?1 AND (?2 IS NULL OR user = ?2);",
+            id,
+            user
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Gets all the auto-responders, oldest first
+    pub fn responders(&self) -> &[Arc<Responder>] {
+        &self.responders
+    }
+
+    /// Adds an auto-responder straight away, returning its id
+    pub async fn add_responder(&mut self, mut responder: Responder) -> FlopResult<i64> {
+        let owner = responder.owner.get() as i64;
+        let guild = responder.guild.map(|x| x.get() as i64);
+        let channel = responder.channel.map(|x| x.get() as i64);
+        let trigger = responder.trigger.kind();
+        let pattern = responder.trigger.pattern();
+        let action = responder.action.kind();
+        let body = responder.action.body();
+        let cooldown = responder.cooldown.as_secs() as i64;
+        responder.id = sqlx::query_scalar!(
+            "INSERT INTO responders(owner, guild, channel, trigger, pattern, action, body, cooldown, counts)
+             VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?)
+             RETURNING id;",
+            owner,
+            guild,
+            channel,
+            trigger,
+            pattern,
+            action,
+            body,
+            cooldown,
+            responder.counts
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let id = responder.id;
+        self.responders.push(Arc::new(responder));
+        Ok(id)
+    }
+
+    /// Sets if an auto-responder's reactions are counted, returning if it exists
+    pub async fn set_responder_counts(&mut self, id: i64, counts: bool) -> FlopResult<bool> {
+        let Some(responder) = self.responders.iter_mut().find(|x| x.id == id) else {
+            return Ok(false);
+        };
+        sqlx::query!("UPDATE responders SET counts = ? WHERE id = ?;", counts, id)
+            .execute(&self.pool)
+            .await?;
+
+        *responder = Arc::new(
+            Responder::new(
+                responder.id,
+                responder.owner,
+                responder.guild,
+                responder.channel,
+                responder.trigger.clone(),
+                responder.action.clone(),
+                responder.cooldown,
+            )
+            .with_counts(counts),
+        );
+        Ok(true)
+    }
+
+    /// Sets how long an auto-responder waits between responses, returning if it exists
+    pub async fn set_responder_cooldown(
+        &mut self,
+        id: i64,
+        cooldown: Duration,
+    ) -> FlopResult<bool> {
+        let Some(responder) = self.responders.iter_mut().find(|x| x.id == id) else {
+            return Ok(false);
+        };
+        let secs = cooldown.as_secs() as i64;
+        sqlx::query!("UPDATE responders SET cooldown = ? WHERE id = ?;", secs, id)
+            .execute(&self.pool)
+            .await?;
+
+        *responder = Arc::new(
+            Responder::new(
+                responder.id,
+                responder.owner,
+                responder.guild,
+                responder.channel,
+                responder.trigger.clone(),
+                responder.action.clone(),
+                cooldown,
+            )
+            .with_counts(responder.counts),
+        );
+        Ok(true)
+    }
+
+    /// Deletes an auto-responder straight away, returning if it existed
+    pub async fn delete_responder(&mut self, id: i64) -> FlopResult<bool> {
+        sqlx::query!("DELETE FROM responders WHERE id = ?;", id)
+            .execute(&self.pool)
+            .await?;
+        let len = self.responders.len();
+        self.responders.retain(|x| x.id != id);
+        Ok(self.responders.len() != len)
+    }
+
+    /// Gets the GitHub repo issue references are expanded from in a guild, if there is one
+    pub fn github_repo(&self, guild: GuildId) -> Option<&str> {
+        self.github_repos.get(&guild).map(String::as_str)
+    }
+
+    /// Sets the GitHub repo for a guild straight away, [`None`] turns off expanding references
+    pub async fn set_github_repo(
+        &mut self,
+        guild: GuildId,
+        repo: Option<String>,
+    ) -> FlopResult<()> {
+        let id = guild.get() as i64;
+        match repo {
+            Some(repo) => {
+                sqlx::query!(
+                    "INSERT INTO github_repos(guild, repo) VALUES(?, ?)
+                     ON CONFLICT(guild) DO UPDATE SET repo = excluded.repo;",
+                    id,
+                    repo
+                )
+                .execute(&self.pool)
+                .await?;
+                self.github_repos.insert(guild, repo);
+            }
+            None => {
+                sqlx::query!("DELETE FROM github_repos WHERE guild = ?;", id)
+                    .execute(&self.pool)
+                    .await?;
+                self.github_repos.remove(&guild);
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the saved data of every command of a type, including ones that failed to load
+    pub async fn command_data(&self, ty: &str) -> Vec<Vec<u8>> {
+        let mut data = Vec::new();
+        for cmd in self.commands.values() {
+            let cmd_lock = cmd.lock().await;
+            if cmd_lock.ty == ty {
+                data.extend(cmd_lock.node.save());
+            }
+        }
+        data
+    }
+
+    /// Gets all the commands that failed to load, sorted by registry then name
+    pub async fn broken_commands(&self) -> Vec<BrokenInfo> {
+        let mut broken = Vec::new();
+        for ((registry, name), cmd) in &self.commands {
+            let cmd_lock = cmd.lock().await;
+            if let CmdNode::Broken { error, .. } = &cmd_lock.node {
+                broken.push(BrokenInfo {
+                    registry: registry.clone(),
+                    name: name.clone(),
+                    ty: cmd_lock.ty.clone(),
+                    error: error.clone(),
+                });
+            }
+        }
+        broken.sort_unstable_by(|a, b| (&a.registry, &a.name).cmp(&(&b.registry, &b.name)));
+        broken
+    }
+
+    /// Tries to load all the commands that failed to load again, returning how many were fixed
+    pub async fn retry_broken(&self, cli: &Cli) -> usize {
+        let mut fixed = 0;
+        for ((registry, name), cmd) in &self.commands {
+            let mut cmd_lock = cmd.lock().await;
+            let entry = &mut *cmd_lock;
+            let CmdNode::Broken { data, error } = &mut entry.node else {
+                continue;
+            };
+            match CmdNode::load(&entry.ty, data, cli) {
+                Ok(node) => {
+                    info!("Loaded previously broken command {registry}:{name}");
+                    entry.node = node;
+                    fixed += 1;
+                }
+                Err(e) => *error = format!("{e}"),
+            }
+        }
+        fixed
+    }
+
+    /// Gets the names of all the commands in a registry, sorted
+    pub fn list_commands(&self, registry: &str) -> Vec<String> {
+        let mut names = self
+            .commands
+            .keys()
+            .filter(|(reg, _)| reg == registry)
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Checks if a registry exists
+    pub fn registry_exists(&self, registry: &str) -> bool {
+        self.registries.contains_key(registry)
+    }
+
     /// Checks if a command exists
     pub fn command_exists(&self, registry: String, name: &str) -> bool {
         self.commands.contains_key(&(registry, name.to_lowercase()))
@@ -514,6 +1219,26 @@ impl FlopDB {
     }
 }
 
+/// Applies any migrations the db hasn't seen yet
+async fn migrate(pool: &Pool<Sqlite>) -> FlopResult<()> {
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version;")
+        .fetch_one(pool)
+        .await?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let mut tx = pool.begin().await?;
+        sqlx::query(migration).execute(&mut *tx).await?;
+        // PRAGMAs can't be bound, so format the version in
+        sqlx::query(&format!("PRAGMA user_version = {};", i + 1))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        info!("Applied database migration {}", i + 1);
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Default)]
 /// The result from [`canonicalise_command`]
 pub struct CanonicalsedResult {
@@ -549,6 +1274,10 @@ pub struct CommandEntry {
     added: i64,
     registry: String,
     node: CmdNode,
+    uses: i64,
+    last_used: Option<i64>,
+    editor: Option<UserId>,
+    edited: Option<i64>,
 }
 
 impl CommandEntry {
@@ -557,6 +1286,11 @@ impl CommandEntry {
         &mut self.node
     }
 
+    /// Gets the id of the command in the db, if it has been inserted yet
+    pub fn get_id(&self) -> Option<i64> {
+        self.id
+    }
+
     /// Gets the owner of the command
     pub fn get_owner(&self) -> &UserId {
         &self.owner
@@ -581,17 +1315,53 @@ impl CommandEntry {
     pub fn get_name(&self) -> &str {
         &self.name
     }
+
+    /// Gets how many times the command has been ran
+    pub fn get_uses(&self) -> i64 {
+        self.uses
+    }
+
+    /// Gets who last edited the command and when, in unix time
+    pub fn get_edited(&self) -> Option<(UserId, i64)> {
+        self.editor.zip(self.edited)
+    }
+
+    /// Gets when the command was last ran, in unix time
+    pub fn get_last_used(&self) -> Option<i64> {
+        self.last_used
+    }
+
+    /// Records that the command has been ran
+    pub fn add_use(&mut self) {
+        self.uses += 1;
+        self.last_used = Some(Timestamp::now().unix_timestamp());
+    }
+
+    /// Sets the type of the command, for when its node is replaced
+    pub fn set_type(&mut self, ty: String) {
+        self.ty = ty;
+    }
+
+    /// Records that the command was edited by a user
+    pub fn set_edited(&mut self, editor: UserId) {
+        self.editor = Some(editor);
+        self.edited = Some(Timestamp::now().unix_timestamp());
+    }
 }
 
 #[derive(Debug)]
 /// The actual type of the command node
 pub enum CmdNode {
     /// An actual command that can be executed
-    Cmd(Box<dyn ExtendedCommand + Send + Sync>),
+    ///
+    /// Shared so it can run without holding the lock on its entry
+    Cmd(Arc<dyn ExtendedCommand + Send + Sync>),
     /// A seperate subregistry
     Subregistry(String),
     /// A symlink to anothe command
     Symlink { reg: String, name: String },
+    /// A command that failed to load, kept with its original data so it isn't lost
+    Broken { data: Vec<u8>, error: String },
 }
 
 impl CmdNode {
@@ -605,6 +1375,7 @@ impl CmdNode {
             Self::Symlink { reg, name } => {
                 Some(rmp_serde::to_vec(&(reg, name)).unwrap_or_default())
             }
+            Self::Broken { data, .. } => Some(data.clone()),
         }
     }
 
@@ -618,11 +1389,86 @@ impl CmdNode {
                     name: data.name,
                 }
             }
-            _ => Self::Cmd(command::construct(ty, data, cli)?),
+            _ => command::construct(ty, data, cli)?.into(),
         })
     }
 }
 
+#[derive(Debug, Clone)]
+/// A command that failed to load, see [`CmdNode::Broken`]
+pub struct BrokenInfo {
+    pub registry: String,
+    pub name: String,
+    pub ty: String,
+    pub error: String,
+}
+
+#[derive(Debug)]
+/// A row of the schedules table, see [`Schedule`]
+struct ScheduleRow {
+    id: i64,
+    owner: i64,
+    guild: Option<i64>,
+    channel: i64,
+    action: String,
+    body: String,
+    cron: Option<String>,
+    next_run: i64,
+    paused: bool,
+}
+
+impl ScheduleRow {
+    fn into_schedule(self) -> Option<Schedule> {
+        let Some(action) = ScheduleAction::from_parts(&self.action, self.body) else {
+            error!(
+                "Schedule {} has an unknown action `{}`",
+                self.id, self.action
+            );
+            return None;
+        };
+        Some(Schedule {
+            id: self.id,
+            owner: UserId::from(self.owner as u64),
+            guild: self.guild.map(|x| GuildId::from(x as u64)),
+            channel: ChannelId::from(self.channel as u64),
+            action,
+            cron: self.cron,
+            next_run: self.next_run,
+            paused: self.paused,
+        })
+    }
+}
+
+#[derive(Debug)]
+/// A row of the reminders table, see [`Reminder`]
+struct ReminderRow {
+    id: i64,
+    user: i64,
+    guild: Option<i64>,
+    channel: i64,
+    message: i64,
+    text: String,
+    added: i64,
+    due: i64,
+    dm: bool,
+}
+
+impl From<ReminderRow> for Reminder {
+    fn from(row: ReminderRow) -> Self {
+        Self {
+            id: row.id,
+            user: UserId::from(row.user as u64),
+            guild: row.guild.map(|x| GuildId::from(x as u64)),
+            channel: ChannelId::from(row.channel as u64),
+            message: MessageId::from(row.message as u64),
+            text: row.text,
+            added: row.added,
+            due: row.due,
+            dm: row.dm,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 struct SymlinkData {
     registry: String,
@@ -631,7 +1477,7 @@ struct SymlinkData {
 
 impl From<Box<dyn ExtendedCommand + Send + Sync>> for CmdNode {
     fn from(value: Box<dyn ExtendedCommand + Send + Sync>) -> Self {
-        Self::Cmd(value)
+        Self::Cmd(value.into())
     }
 }
 
@@ -698,6 +1544,14 @@ impl FlopRole {
     }
 }
 
+#[derive(Debug, Default)]
+/// Changes that still need to be written to the db
+pub struct Unsynced {
+    pub commands: HashSet<(String, String)>,
+    pub removed: Vec<i64>,
+    pub roles: Vec<(UserId, SyncState)>,
+}
+
 /// An enum to represent the state somthing is regarding being synced
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub enum SyncState {
@@ -1,4 +1,5 @@
 use serenity::all::{Mention, UserId};
+use time::{Date, Month, OffsetDateTime, Time};
 use tracing::error;
 
 use std::str::FromStr;
@@ -20,3 +21,162 @@ pub fn try_get_user(text: &str) -> Option<UserId> {
         _ => None,
     }
 }
+
+/// Parses a duration like `90s`, `10m`, `2h30m` or `1w2d` into seconds
+pub fn parse_duration(text: &str) -> Option<i64> {
+    let mut total: i64 = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 60 * 60 * 24,
+            'w' => 60 * 60 * 24 * 7,
+            _ => return None,
+        };
+        let amount = number.parse::<i64>().ok()?;
+        total = total.checked_add(amount.checked_mul(unit)?)?;
+        number.clear();
+    }
+    // A trailing number without a unit isn't a duration
+    (number.is_empty() && total > 0).then_some(total)
+}
+
+/// Parses a duration written as a number and a unit word, like `5 minutes` or `1 hr`, into seconds
+pub fn parse_spaced_duration(amount: &str, unit: &str) -> Option<i64> {
+    let unit = match unit.to_ascii_lowercase().as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60,
+        "d" | "day" | "days" => 60 * 60 * 24,
+        "w" | "week" | "weeks" => 60 * 60 * 24 * 7,
+        _ => return None,
+    };
+    if amount.is_empty() || !amount.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    amount
+        .parse::<i64>()
+        .ok()
+        .filter(|x| *x > 0)?
+        .checked_mul(unit)
+}
+
+/// Parses when something should happen into a unix timestamp
+///
+/// This can be a duration from `now`, a discord timestamp like `<t:1700000000:R>`,
+/// a UTC date and time like `2024-05-01T14:30` or `2024-05-01`,
+/// or a UTC time like `14:30`, which is the next time it is that time.
+/// Bare numbers aren't taken as timestamps, as they are more likely a duration missing its unit
+pub fn parse_time(text: &str, now: i64) -> Option<i64> {
+    if let Some(inner) = text.strip_prefix("<t:").and_then(|x| x.strip_suffix('>')) {
+        let stamp = inner.split_once(':').map_or(inner, |(stamp, _)| stamp);
+        return stamp.parse().ok();
+    }
+    if let Some(duration) = parse_duration(text) {
+        return now.checked_add(duration);
+    }
+
+    let (date, time) = match text.split_once(['T', 't']) {
+        Some((date, time)) => (Some(date), Some(time)),
+        None if text.contains(':') => (None, Some(text)),
+        None => (Some(text), None),
+    };
+    let time = match time {
+        Some(time) => parse_clock(time)?,
+        None => Time::MIDNIGHT,
+    };
+    match date {
+        Some(date) => Some(
+            parse_date(date)?
+                .with_time(time)
+                .assume_utc()
+                .unix_timestamp(),
+        ),
+        None => {
+            // Today if it hasn't happened yet, otherwise tomorrow
+            let today = OffsetDateTime::from_unix_timestamp(now).ok()?.date();
+            let stamp = today.with_time(time).assume_utc().unix_timestamp();
+            Some(if stamp > now {
+                stamp
+            } else {
+                stamp + 60 * 60 * 24
+            })
+        }
+    }
+}
+
+/// Parses a date like `2024-05-01`
+fn parse_date(text: &str) -> Option<Date> {
+    let mut parts = text.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = Month::try_from(parts.next()?.parse::<u8>().ok()?).ok()?;
+    let day = parts.next()?.parse().ok()?;
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+/// Parses a time like `14:30` or `14:30:15`
+fn parse_clock(text: &str) -> Option<Time> {
+    let mut parts = text.splitn(3, ':');
+    let hour = parts.next()?.parse().ok()?;
+    let minute = parts.next()?.parse().ok()?;
+    let second = parts.next().map_or(Some(0), |x| x.parse().ok())?;
+    Time::from_hms(hour, minute, second).ok()
+}
+
+/// Cuts text down to at most `limit` characters, ending it with `…` if anything was cut
+pub fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().nth(limit).is_none() {
+        return text.to_owned();
+    }
+    let cut = text
+        .char_indices()
+        .nth(limit.saturating_sub(1))
+        .map_or(text.len(), |(i, _)| i);
+    format!("{}…", &text[..cut])
+}
+
+/// Splits the first word off some text, keeping the rest as is
+pub fn next_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], &text[i..]),
+        None => (text, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("2h30m"), Some(9000));
+        assert_eq!(parse_duration("1w2d"), Some(777_600));
+        assert_eq!(parse_duration("5"), None);
+        assert_eq!(parse_spaced_duration("5", "minutes"), Some(300));
+        assert_eq!(parse_spaced_duration("1", "HR"), Some(3600));
+        assert_eq!(parse_spaced_duration("+5", "m"), None);
+        assert_eq!(parse_spaced_duration("5", "to"), None);
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("10m", NOW), Some(NOW + 600));
+        assert_eq!(parse_time("<t:1800000000:R>", NOW), Some(1_800_000_000));
+        assert_eq!(parse_time("2024-05-01T14:30", NOW), Some(1_714_573_800));
+        // 2023-11-14 22:13:20 UTC, so 14:30 is tomorrow and 23:00 is today
+        assert_eq!(parse_time("14:30", NOW), Some(1_700_058_600));
+        assert_eq!(parse_time("23:00", NOW), Some(1_700_002_800));
+        // Bare numbers are a duration without a unit, not a timestamp
+        assert_eq!(parse_time("5", NOW), None);
+        assert_eq!(parse_time("1800000000", NOW), None);
+    }
+}